@@ -0,0 +1,221 @@
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Settings read from the JSON file given to `--config`, e.g.
+/// `{"styles": [{"name": "error", "base": "*Exception", "style": "fill:#fdd"}]}`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// What goes above the body of Mermaid diagrams.
+    #[serde(default)]
+    pub header: Header,
+    /// How to style classes in Mermaid class diagrams.
+    #[serde(default, deserialize_with = "styles")]
+    pub styles: Vec<touml::StyleRule>,
+}
+
+/// Reads each style rule on its own, so that errors name the rule at fault.
+fn styles<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<touml::StyleRule>, D::Error> {
+    Vec::<Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|rule| {
+            let name = rule["name"].as_str().unwrap_or_default().to_string();
+            serde_json::from_value(rule)
+                .map_err(|e| serde::de::Error::custom(format!("invalid style rule `{name}`: {e}")))
+        })
+        .collect()
+}
+
+/// Which way Mermaid lays out a diagram, e.g. `LR` for left to right.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[value(rename_all = "UPPER")]
+pub enum Direction {
+    TB,
+    BT,
+    LR,
+    RL,
+}
+
+/// Options for the front matter, directives and opening lines of Mermaid
+/// diagrams, given on the command line or under `header` in the config.
+#[derive(Args, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Header {
+    /// Direction to lay Mermaid diagrams out in.
+    #[arg(long, value_enum)]
+    pub direction: Option<Direction>,
+
+    /// Title of Mermaid diagrams, written as front matter.
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Mermaid theme, e.g. `neutral`, `dark` or `forest`.
+    #[arg(long)]
+    pub theme: Option<String>,
+
+    /// Hide the members box of Mermaid classes without any fields or methods.
+    // A flag without a value on the command line, which the config may set to
+    // `false`, so that it never takes the path after it for its value.
+    #[arg(long, num_args = 0, default_missing_value = "true")]
+    pub hide_empty_members_box: Option<bool>,
+
+    /// Further Mermaid configuration for the `%%{init: ...}%%` directive, as a JSON object, e.g. `{"look": "handDrawn"}`.
+    #[arg(long, value_parser = parse_object)]
+    pub init: Option<Value>,
+
+    /// Lines to write at the start of Mermaid diagrams, e.g. `classDef default fill:#fff`. May be repeated.
+    #[arg(long)]
+    #[serde(default)]
+    pub preamble: Vec<String>,
+}
+
+fn parse_object(s: &str) -> Result<Value, String> {
+    match serde_json::from_str(s) {
+        Ok(value @ Value::Object(_)) => Ok(value),
+        Ok(_) => Err("expected a JSON object".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+impl Header {
+    /// Fills in whatever is unset from `fallback`, e.g. the config file, after
+    /// its preamble.
+    pub fn or(self, fallback: Header) -> Header {
+        Header {
+            direction: self.direction.or(fallback.direction),
+            title: self.title.or(fallback.title),
+            theme: self.theme.or(fallback.theme),
+            hide_empty_members_box: self
+                .hide_empty_members_box
+                .or(fallback.hide_empty_members_box),
+            init: self.init.or(fallback.init),
+            preamble: [fallback.preamble, self.preamble].concat(),
+        }
+    }
+
+    fn init(&self) -> Option<Value> {
+        let mut init = self.init.clone().unwrap_or_else(|| json!({}));
+        if let Some(theme) = &self.theme {
+            init["theme"] = json!(theme);
+        }
+        if let Some(hide) = self.hide_empty_members_box {
+            // Mermaid keeps the options of class diagrams under `class`.
+            if !init["class"].is_object() {
+                init["class"] = json!({});
+            }
+            init["class"]["hideEmptyMembersBox"] = json!(hide);
+        }
+        init.as_object()
+            .is_some_and(|o| !o.is_empty())
+            .then_some(init)
+    }
+
+    /// Prints everything up to the body of a diagram of the given type, e.g.
+    /// `classDiagram`. Flowcharts take their direction on the same line,
+    /// left to right unless told otherwise.
+    pub fn print(&self, diagram: &str) -> String {
+        let mut result = String::new();
+        if let Some(title) = &self.title {
+            // A JSON string is also a valid YAML one.
+            result.push_str(&format!("---\ntitle: {}\n---\n", json!(title)));
+        }
+        if let Some(init) = self.init() {
+            result.push_str(&format!("%%{{init: {init}}}%%\n"));
+        }
+        let mut lines = Vec::new();
+        match diagram {
+            "flowchart" => {
+                let direction = self.direction.unwrap_or(Direction::LR);
+                result.push_str(&format!("flowchart {direction:?}\n"));
+            }
+            _ => {
+                result.push_str(diagram);
+                result.push('\n');
+                if let Some(direction) = self.direction {
+                    lines.push(format!("direction {direction:?}"));
+                }
+            }
+        }
+        lines.extend(self.preamble.iter().cloned());
+        for line in lines {
+            result.push_str(&format!("    {line}\n"));
+        }
+        result.push('\n');
+        result
+    }
+}
+
+impl Config {
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = serde_json::from_str(
+            r#"{"styles": [{"name": "abstract", "stereotype": "abstract", "style": "stroke-dasharray:5 5"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.styles.len(), 1);
+        assert_eq!(
+            config.styles[0].stereotype,
+            Some(touml::ClassKind::Abstract)
+        );
+
+        assert!(serde_json::from_str::<Config>(r#"{"style": []}"#).is_err());
+
+        let error = serde_json::from_str::<Config>(
+            r#"{"styles": [{"name": "models", "module": "pkg.[", "style": "fill:#fff"}]}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("invalid style rule `models`: invalid pattern `pkg.[`"));
+    }
+
+    #[test]
+    fn test_print_header() {
+        assert_eq!(Header::default().print("classDiagram"), "classDiagram\n\n");
+        assert_eq!(Header::default().print("flowchart"), "flowchart LR\n\n");
+
+        let config: Config = serde_json::from_str(
+            r#"{"header": {"title": "Orders: v2", "theme": "dark", "init": {"look": "handDrawn"}, "preamble": ["%% generated"]}}"#,
+        )
+        .unwrap();
+        let header = Header {
+            direction: Some(Direction::TB),
+            hide_empty_members_box: Some(true),
+            preamble: vec!["classDef default fill:#fff".to_string()],
+            ..Default::default()
+        }
+        .or(config.header);
+
+        #[rustfmt::skip]
+        let expected = [
+            "---",
+            "title: \"Orders: v2\"",
+            "---",
+            "%%{init: {\"class\":{\"hideEmptyMembersBox\":true},\"look\":\"handDrawn\",\"theme\":\"dark\"}}%%",
+            "classDiagram",
+            "    direction TB",
+            "    %% generated",
+            "    classDef default fill:#fff",
+            "",
+            "",
+        ].join("\n");
+        assert_eq!(header.print("classDiagram"), expected);
+    }
+}
@@ -0,0 +1,313 @@
+use anyhow::{self, Result};
+use std::path::Path;
+
+static START: &str = "touml:start";
+static END: &str = "touml:end";
+
+/// The markup a document is written in, which decides how markers and
+/// diagrams are written within it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// Markers are HTML comments, e.g. `<!-- touml:start path=src -->`, around
+    /// a fenced `mermaid` block.
+    Markdown,
+
+    /// Markers are comments, e.g. `.. touml:start path=src`, around a
+    /// `.. mermaid::` directive.
+    Rst,
+}
+
+impl Syntax {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("rst") => Self::Rst,
+            _ => Self::Markdown,
+        }
+    }
+
+    /// Returns the options of a start marker, if the line is one.
+    fn start_marker<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let line = line.trim();
+        match self {
+            Self::Markdown => line
+                .strip_prefix("<!--")?
+                .strip_suffix("-->")?
+                .trim()
+                .strip_prefix(START),
+            Self::Rst => line.strip_prefix("..")?.trim().strip_prefix(START),
+        }
+        .map(str::trim)
+    }
+
+    fn is_end_marker(&self, line: &str) -> bool {
+        let line = line.trim();
+        match self {
+            Self::Markdown => line
+                .strip_prefix("<!--")
+                .and_then(|l| l.strip_suffix("-->"))
+                .is_some_and(|l| l.trim() == END),
+            Self::Rst => line.strip_prefix("..").is_some_and(|l| l.trim() == END),
+        }
+    }
+
+    /// Wraps a diagram in a fenced block or directive, for Mermaid to render.
+    pub fn wrap(&self, diagram: &str) -> String {
+        match self {
+            Self::Markdown => format!("```mermaid\n{}\n```\n", diagram.trim_end()),
+            Self::Rst => {
+                let body = diagram
+                    .trim_end()
+                    .lines()
+                    .map(|l| {
+                        if l.trim().is_empty() {
+                            String::new()
+                        } else {
+                            format!("   {l}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("\n.. mermaid::\n\n{body}\n\n")
+            }
+        }
+    }
+}
+
+/// Tracks whether lines sit in a code block, where markers are only examples
+/// of markers rather than markers themselves.
+#[derive(Default)]
+struct Literal {
+    /// The character and length of the fence of an open Markdown code block.
+    fence: Option<(char, usize)>,
+    /// The indentation of the line introducing an open RST literal block.
+    block: Option<usize>,
+}
+
+impl Literal {
+    /// Reads a line, returning whether it opens, closes or sits in a block.
+    fn skip(&mut self, line: &str, syntax: Syntax) -> bool {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        match syntax {
+            Syntax::Markdown => {
+                let run = |c: char| trimmed.chars().take_while(|&t| t == c).count();
+                if let Some((c, n)) = self.fence {
+                    let closing = run(c);
+                    if closing >= n && trimmed[closing..].trim().is_empty() {
+                        self.fence = None;
+                    }
+                    return true;
+                }
+                if indent < 4 {
+                    if let Some(fence) = ['`', '~']
+                        .into_iter()
+                        .map(|c| (c, run(c)))
+                        .find(|&(_, n)| n >= 3)
+                    {
+                        self.fence = Some(fence);
+                        return true;
+                    }
+                }
+                false
+            }
+            Syntax::Rst => {
+                if let Some(base) = self.block {
+                    if trimmed.trim().is_empty() || indent > base {
+                        return true;
+                    }
+                    self.block = None;
+                }
+                // e.g. `Example::` or `.. code-block:: rst`.
+                if trimmed.trim_end().ends_with("::")
+                    || (trimmed.starts_with("..") && trimmed.contains(":: "))
+                {
+                    self.block = Some(indent);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Splits marker options into arguments, e.g. `path=src exclude-classes="A B"`
+/// into `["src", "--exclude-classes", "A B"]`.
+pub fn marker_args(options: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for c in options.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    let mut args = Vec::new();
+    for token in tokens {
+        match token.split_once('=') {
+            Some(("path", value)) => args.push(value.to_string()),
+            Some((key, value)) => {
+                args.push(format!("--{key}"));
+                args.push(value.to_string());
+            }
+            None => args.push(format!("--{token}")),
+        }
+    }
+    args
+}
+
+/// Regenerates the diagram between each pair of markers in a document, with
+/// `render` turning the options of a start marker into a diagram.
+pub fn update(
+    contents: &str,
+    syntax: Syntax,
+    render: impl Fn(&str) -> Result<String>,
+) -> Result<String> {
+    let mut result = String::with_capacity(contents.len());
+    let mut lines = contents.split_inclusive('\n');
+    let mut literal = Literal::default();
+    while let Some(line) = lines.next() {
+        result.push_str(line);
+        if literal.skip(line, syntax) {
+            continue;
+        }
+        let Some(options) = syntax.start_marker(line) else {
+            continue;
+        };
+
+        let diagram = render(options)?;
+        let mut closed = false;
+        for line in lines.by_ref() {
+            if syntax.is_end_marker(line) {
+                if !result.ends_with('\n') {
+                    result.push('\n');
+                }
+                result.push_str(&syntax.wrap(&diagram));
+                result.push_str(line);
+                closed = true;
+                break;
+            }
+        }
+        if !closed {
+            anyhow::bail!("found `{START}` marker without a matching `{END}`.");
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_args() {
+        assert_eq!(
+            marker_args(r#"path=src/models exclude-classes="A* B*" rev=HEAD"#),
+            ["src/models", "--exclude-classes", "A* B*", "--rev", "HEAD"]
+        );
+    }
+
+    #[test]
+    fn test_update_markdown() {
+        #[rustfmt::skip]
+        let doc = [
+            "# Models",
+            "<!-- touml:start path=src -->",
+            "```mermaid",
+            "classDiagram",
+            "    class Stale",
+            "```",
+            "<!-- touml:end -->",
+            "Trailing text.",
+        ].join("\n");
+
+        let updated = update(&doc, Syntax::Markdown, |options| {
+            Ok(format!("classDiagram\n    class Fresh\n%% {options}\n"))
+        })
+        .unwrap();
+        assert_eq!(
+            updated,
+            [
+                "# Models",
+                "<!-- touml:start path=src -->",
+                "```mermaid",
+                "classDiagram",
+                "    class Fresh",
+                "%% path=src",
+                "```",
+                "<!-- touml:end -->",
+                "Trailing text.",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_update_rst() {
+        let doc = ".. touml:start path=src\n\n.. touml:end\n";
+        let updated = update(doc, Syntax::Rst, |_| {
+            Ok("classDiagram\n\n    class A\n".into())
+        });
+        assert_eq!(
+            updated.unwrap(),
+            ".. touml:start path=src\n\n.. mermaid::\n\n   classDiagram\n\n       class A\n\n.. touml:end\n"
+        );
+    }
+
+    #[test]
+    fn test_update_skips_code_blocks() {
+        #[rustfmt::skip]
+        let doc = [
+            "````markdown",
+            "<!-- touml:start path=src -->",
+            "```",
+            "<!-- touml:end -->",
+            "````",
+            "~~~",
+            "<!-- touml:start path=src -->",
+            "~~~",
+            "<!-- touml:start path=real -->",
+            "<!-- touml:end -->",
+            "",
+        ].join("\n");
+        let updated = update(&doc, Syntax::Markdown, |options| {
+            Ok(format!("%% {options}"))
+        })
+        .unwrap();
+        assert_eq!(updated.matches("```mermaid").count(), 1);
+        assert!(updated.contains("```mermaid\n%% path=real\n```"));
+
+        #[rustfmt::skip]
+        let doc = [
+            "For example::",
+            "",
+            "    .. touml:start path=src",
+            "    .. touml:end",
+            "",
+            ".. code-block:: rst",
+            "",
+            "   .. touml:start path=src",
+            "",
+            "Done.",
+            "",
+        ].join("\n");
+        assert_eq!(
+            update(&doc, Syntax::Rst, |_| Ok(String::new())).unwrap(),
+            doc
+        );
+    }
+
+    #[test]
+    fn test_update_unclosed() {
+        let doc = "<!-- touml:start path=src -->\n";
+        assert!(update(doc, Syntax::Markdown, |_| Ok(String::new())).is_err());
+    }
+}
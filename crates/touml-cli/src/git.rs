@@ -0,0 +1,325 @@
+use anyhow::{self, Context, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Inclusive ranges of lines, per file, that differ between two revisions.
+pub type ChangedLines = HashMap<PathBuf, Vec<(usize, usize)>>;
+
+/// Where to run git for a given walk root. Git cannot be run "in" a file, so a
+/// file root runs in its parent directory and is passed as the pathspec.
+struct Location {
+    dir: PathBuf,
+    prefix: PathBuf,
+    pathspec: PathBuf,
+}
+
+impl Location {
+    fn new(root: &Path) -> Self {
+        if root.is_dir() {
+            return Self {
+                dir: root.to_path_buf(),
+                prefix: root.to_path_buf(),
+                pathspec: PathBuf::from("."),
+            };
+        }
+        let prefix = root.parent().unwrap_or(Path::new("")).to_path_buf();
+        let dir = if prefix.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            prefix.clone()
+        };
+        let pathspec = root.file_name().map(PathBuf::from).unwrap_or_default();
+        Self {
+            dir,
+            prefix,
+            pathspec,
+        }
+    }
+
+    /// Maps a path printed by git, relative to `dir`, onto the walk root so
+    /// that it lines up with paths from `utils::get_file_paths`.
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.prefix.join(path)
+    }
+
+    fn command(&self) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(&self.dir);
+        cmd
+    }
+}
+
+fn run(mut cmd: Command) -> Result<Vec<u8>> {
+    let output = cmd.output().context("failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Reads every file under `root` as of the revision `rev`, from the object
+/// database of the repository containing it.
+pub fn read_tree(root: &Path, rev: &str) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let loc = Location::new(root);
+
+    let mut cmd = loc.command();
+    cmd.args(["ls-tree", "-r", "-z", rev, "--"])
+        .arg(&loc.pathspec);
+    let listing = run(cmd)?;
+
+    // Each record reads `<mode> <type> <object>\t<path>`.
+    let mut blobs = Vec::new();
+    for record in listing.split(|&b| b == 0).filter(|r| !r.is_empty()) {
+        let record = String::from_utf8_lossy(record);
+        let Some((meta, path)) = record.split_once('\t') else {
+            continue;
+        };
+        let mut meta = meta.split(' ');
+        if let (Some(_), Some("blob"), Some(object)) = (meta.next(), meta.next(), meta.next()) {
+            blobs.push((loc.resolve(path), object.to_string()));
+        }
+    }
+
+    let mut child = loc
+        .command()
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run git")?;
+
+    // Feed object names from another thread, lest both pipes fill up.
+    let mut stdin = child.stdin.take().unwrap();
+    let objects = blobs.iter().map(|(_, o)| o.clone()).collect::<Vec<_>>();
+    let writer = std::thread::spawn(move || -> std::io::Result<()> {
+        for object in objects {
+            writeln!(stdin, "{object}")?;
+        }
+        Ok(())
+    });
+
+    let mut reader = BufReader::new(child.stdout.take().unwrap());
+    let mut files = Vec::with_capacity(blobs.len());
+    for (path, _) in blobs {
+        // Each object reads `<object> <type> <size>\n<contents>\n`.
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let size = header
+            .split_whitespace()
+            .nth(2)
+            .and_then(|s| s.parse::<usize>().ok())
+            .with_context(|| format!("unexpected output from git cat-file: {header}"))?;
+
+        let mut contents = vec![0; size + 1];
+        reader.read_exact(&mut contents)?;
+        contents.truncate(size);
+        files.push((path, contents));
+    }
+
+    writer.join().unwrap()?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(files)
+}
+
+/// Lists the lines under `root` that changed since the revision `since`, up to
+/// `rev` or the working tree if none is given.
+pub fn changed_lines(root: &Path, since: &str, rev: Option<&str>) -> Result<ChangedLines> {
+    let loc = Location::new(root);
+
+    let mut cmd = loc.command();
+    cmd.args([
+        "-c",
+        "core.quotePath=false",
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-prefix",
+        "--relative",
+        "-U0",
+        since,
+    ]);
+    if let Some(rev) = rev {
+        cmd.arg(rev);
+    }
+    cmd.arg("--").arg(&loc.pathspec);
+    let diff = String::from_utf8_lossy(&run(cmd)?).into_owned();
+
+    Ok(parse_diff(&diff, |p| loc.resolve(p)))
+}
+
+/// Resolves a revision under `root` to the full hash of its commit.
+pub fn commit(root: &Path, rev: &str) -> Result<String> {
+    let mut cmd = Location::new(root).command();
+    cmd.args(["rev-parse", "--verify", "--end-of-options"])
+        .arg(format!("{rev}^{{commit}}"));
+    Ok(String::from_utf8_lossy(&run(cmd)?).trim().to_string())
+}
+
+/// Finds the absolute root of the repository containing `root`, as spelt by
+/// `root` itself rather than with symlinks resolved the way git would.
+pub fn toplevel(root: &Path) -> Result<PathBuf> {
+    let loc = Location::new(root);
+    let mut cmd = loc.command();
+    cmd.args(["rev-parse", "--show-prefix"]);
+    let prefix = String::from_utf8_lossy(&run(cmd)?).trim().to_string();
+
+    let mut toplevel = std::path::absolute(&loc.dir)?;
+    for _ in Path::new(&prefix).components() {
+        toplevel.pop();
+    }
+    Ok(toplevel)
+}
+
+/// Reads a path as git prints it in a diff header, which ends with a tab if
+/// the path has spaces, and is quoted with C escapes if it has unusual
+/// characters, e.g., `"caf\303\251.py"`.
+fn unquote(path: &str) -> String {
+    let path = path.strip_suffix('\t').unwrap_or(path);
+    let Some(quoted) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some(d @ '0'..='7') => {
+                let digits = std::iter::once(d)
+                    .chain(chars.by_ref().take(2))
+                    .collect::<String>();
+                bytes.push(u8::from_str_radix(&digits, 8).unwrap_or(b'?'));
+            }
+            Some(c) => bytes.push(c as u8),
+            None => (),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn parse_diff(diff: &str, resolve: impl Fn(&str) -> PathBuf) -> ChangedLines {
+    let mut changes = ChangedLines::new();
+    let mut current = None;
+    // Only right after `--- `, within the header before a file's first hunk,
+    // does `+++ ` name the new file rather than add a line starting `++`.
+    let mut header = false;
+    let mut old_path = false;
+    for line in diff.lines() {
+        if line.starts_with("diff ") {
+            header = true;
+        } else if line.starts_with("@@ ") {
+            header = false;
+        }
+        let after_old_path = std::mem::replace(&mut old_path, header && line.starts_with("--- "));
+
+        if let Some(path) = line.strip_prefix("+++ ").filter(|_| after_old_path) {
+            current = (path != "/dev/null").then(|| resolve(&unquote(path)));
+        } else if let (Some(path), Some(hunk)) = (&current, line.strip_prefix("@@ ")) {
+            // e.g., `@@ -10,2 +12,3 @@`, where the `+` side is the new file.
+            let Some(added) = hunk.split_whitespace().find_map(|t| t.strip_prefix('+')) else {
+                continue;
+            };
+            let (start, count) = match added.split_once(',') {
+                Some((s, c)) => (s.parse().unwrap_or(0), c.parse().unwrap_or(0)),
+                None => (added.parse().unwrap_or(0), 1),
+            };
+            // Pure deletions have no lines of their own, so mark the lines
+            // either side of where they used to be.
+            let range = if count == 0 {
+                (start, start + 1)
+            } else {
+                (start, start + count - 1)
+            };
+            changes.entry(path.clone()).or_default().push(range);
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diff() {
+        #[rustfmt::skip]
+        let diff = [
+            "diff --git models.py models.py",
+            "--- models.py",
+            "+++ models.py",
+            "@@ -3 +3 @@ class Base:",
+            "-    id: int",
+            "+    id: str",
+            "@@ -10,2 +9,0 @@ class Order:",
+            "-    a: int",
+            "-    b: int",
+            "@@ -20,0 +19,3 @@",
+            "diff --git old.py old.py",
+            "--- old.py",
+            "+++ /dev/null",
+            "@@ -1,4 +0,0 @@",
+        ].join("\n");
+
+        let changes = parse_diff(&diff, |p| PathBuf::from("src").join(p));
+        assert_eq!(
+            changes,
+            ChangedLines::from([(
+                PathBuf::from("src/models.py"),
+                vec![(3, 3), (9, 10), (19, 21)]
+            )])
+        );
+    }
+
+    #[test]
+    fn test_parse_diff_headers() {
+        #[rustfmt::skip]
+        let diff = [
+            "diff --git models.py models.py",
+            "--- models.py",
+            "+++ models.py",
+            "@@ -3,0 +4 @@",
+            "+++ counter",
+            "@@ -8 +9 @@",
+            "-    a: int",
+            "+    a: str",
+            "diff --git \"caf\\303\\251.py\" \"caf\\303\\251.py\"",
+            "--- \"caf\\303\\251.py\"",
+            "+++ \"caf\\303\\251.py\"",
+            "@@ -1 +1 @@",
+            "diff --git my models.py my models.py",
+            "--- my models.py\t",
+            "+++ my models.py\t",
+            "@@ -2 +2 @@",
+        ].join("\n");
+
+        let changes = parse_diff(&diff, |p| PathBuf::from(p));
+        assert_eq!(
+            changes,
+            ChangedLines::from([
+                (PathBuf::from("models.py"), vec![(4, 4), (9, 9)]),
+                (PathBuf::from("café.py"), vec![(1, 1)]),
+                (PathBuf::from("my models.py"), vec![(2, 2)]),
+            ])
+        );
+    }
+}
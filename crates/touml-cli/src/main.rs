@@ -1,24 +1,275 @@
+mod config;
+mod embed;
+mod git;
+mod split;
 mod utils;
 
-use anyhow::{self, Result};
-use clap::Parser;
+use anyhow::{self, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use rayon::prelude::*;
-use std::fs::{self, File};
+use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-static OUTPUT_FILENAME: &str = "out.mmd";
+static OUTPUT_FILENAME: &str = "out";
 
 /// A tool to generate mermaid class diagrams from Python source code.
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    #[arg(index(1))]
-    path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
 
-    /// Path to write the output to as an `mmd` file. If unprovided, the output is written to stdout.
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    /// Path to a Python file or directory, or to a `.whl`, `.zip` or `.tar.gz` archive thereof.
+    #[arg(index(1), required = true)]
+    path: Option<PathBuf>,
+
+    /// Path to write the output to, in the format implied by its extension: `.mmd`, `.md` (Mermaid in a fenced block), `.puml`, `.dot`, `.d2`, `.json`, `.html`, `.svg` or `.xmi`. If a directory, the output is written to an `out` file therein, e.g. `out.mmd`. May be repeated to write several formats at once. If unprovided, the output is written to stdout.
     #[arg(short, long)]
-    output: Option<PathBuf>,
+    output: Vec<PathBuf>,
+
+    /// Format of the diagram, for stdout, directories and paths without a known extension. An `.mmd` or `.md` path takes this format if it is a Mermaid one.
+    #[arg(long, value_enum, default_value_t = Format::Mermaid)]
+    format: Format,
+
+    /// Collapse modules into their package at this depth, e.g. 2 for `pkg.models` from `pkg.models.base`.
+    #[arg(long)]
+    package_depth: Option<usize>,
+
+    /// Group classes into a Mermaid namespace per module, or per package with `--package-depth`.
+    #[arg(long)]
+    namespaces: bool,
+
+    /// What to do with parents from the standard library or third parties in Mermaid class diagrams.
+    #[arg(long, value_enum, default_value_t = Externals::Edges)]
+    externals: Externals,
+
+    /// Write subscripted annotations with Mermaid's generics, e.g. `List~int~`, dropping `typing.` and `t.` prefixes.
+    #[arg(long)]
+    generics: bool,
+
+    /// Link each class in Mermaid diagrams to its source, filling in `{rev}`, `{path}`, `{line}` and `{abs_path}`, e.g. `https://git.example/repo/blob/{rev}/{path}#L{line}`.
+    #[arg(long)]
+    link_template: Option<String>,
+
+    /// Path to a JSON file of further settings, such as `styles` mapping selectors (`module` and `base` globs, a `stereotype`, or `external`) to Mermaid `classDef` styles.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    header: config::Header,
+
+    /// Write a diagram per module, package or connected component into each `--output`, taken as a directory, along with an `index.md` linking them.
+    #[arg(long, value_enum)]
+    split_by: Option<split::SplitBy>,
+
+    #[command(flatten)]
+    files: FileArgs,
+
+    #[command(flatten)]
+    classes: ClassArgs,
+
+    #[command(flatten)]
+    focus: FocusArgs,
+
+    /// Git revision to read sources at, instead of the working tree, e.g. `v1.2.0` or `HEAD~3`.
+    #[arg(long)]
+    rev: Option<String>,
+
+    /// Git revision to diff against, keeping only classes whose source has changed since, along with their direct parents and children.
+    #[arg(long)]
+    changed_since: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Mermaid,
+    /// A Mermaid entity relationship diagram of SQLAlchemy, SQLModel and Django models.
+    MermaidEr,
+    /// A Mermaid flowchart of imports between modules.
+    MermaidModules,
+    Plantuml,
+    Dot,
+    D2,
+    Json,
+    /// A self-contained HTML page with an interactive diagram, for viewing offline.
+    Html,
+    /// An SVG image of the class diagram, drawn without Mermaid or a browser.
+    Svg,
+    /// XMI 2.5.1, for import into UML tools such as Enterprise Architect, Papyrus and StarUML.
+    Xmi,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Mermaid | Self::MermaidEr | Self::MermaidModules => "mmd",
+            Self::Plantuml => "puml",
+            Self::Dot => "dot",
+            Self::D2 => "d2",
+            Self::Json => "json",
+            Self::Html => "html",
+            Self::Svg => "svg",
+            Self::Xmi => "xmi",
+        }
+    }
+
+    fn is_mermaid(&self) -> bool {
+        matches!(self, Self::Mermaid | Self::MermaidEr | Self::MermaidModules)
+    }
+}
+
+/// Ways of drawing parents from the standard library or third parties.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Externals {
+    /// Draw them as any other class, declared only through their edges.
+    Edges,
+    /// Draw them as stubs annotated `<<external>>`, styled by whether they come from the standard library or third parties.
+    Stub,
+    /// Draw a single `external` node in their place.
+    Collapse,
+    /// Leave them out, along with their edges.
+    Drop,
+}
+
+impl From<Externals> for touml::Externals {
+    fn from(externals: Externals) -> Self {
+        match externals {
+            Externals::Edges => Self::Edges,
+            Externals::Stub => Self::Stub,
+            Externals::Collapse => Self::Collapse,
+            Externals::Drop => Self::Drop,
+        }
+    }
+}
+
+/// A file to write, and the format to write it in.
+#[derive(Debug, PartialEq)]
+struct Target {
+    path: PathBuf,
+    format: Format,
+    /// Whether to wrap the diagram in a fenced code block, for Markdown.
+    fenced: bool,
+}
+
+impl Target {
+    /// Resolves an `--output` path, defaulting to e.g. `out.mmd` within
+    /// directories and otherwise going by the extension.
+    fn new(output: &Path, format: Format) -> Self {
+        if output.is_dir() {
+            return Self {
+                path: output
+                    .join(OUTPUT_FILENAME)
+                    .with_extension(format.extension()),
+                format,
+                fenced: false,
+            };
+        }
+
+        let extension = output
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let (format, fenced) = match extension.as_str() {
+            "md" | "markdown" if format.is_mermaid() => (format, true),
+            "md" | "markdown" => (Format::Mermaid, true),
+            e if e == format.extension() => (format, false),
+            "mmd" => (Format::Mermaid, false),
+            "puml" => (Format::Plantuml, false),
+            "dot" | "gv" => (Format::Dot, false),
+            "d2" => (Format::D2, false),
+            "json" => (Format::Json, false),
+            "html" | "htm" => (Format::Html, false),
+            "svg" => (Format::Svg, false),
+            "xmi" => (Format::Xmi, false),
+            _ => (format, false),
+        };
+        Self {
+            path: output.to_path_buf(),
+            format,
+            fenced,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare the classes in two Python sources, e.g. two releases of a package.
+    Diff(Box<DiffArgs>),
+
+    /// Check that the diagram written to `--output` is up to date, printing a diff if not.
+    Check(Box<GenerateArgs>),
+
+    /// Regenerate diagrams embedded in Markdown or reStructuredText files between
+    /// `touml:start` and `touml:end` markers, e.g. `<!-- touml:start path=src/models -->`.
+    /// Markers take the same options as the command line, relative to the current directory.
+    Embed(EmbedArgs),
+}
+
+#[derive(Args, Debug)]
+struct EmbedArgs {
+    /// Markdown or reStructuredText files to update.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Check that embedded diagrams are up to date rather than updating them, printing a diff if not.
+    #[arg(long)]
+    check: bool,
+}
+
+/// Options given in an embed marker, parsed as if given on the command line.
+#[derive(Parser, Debug)]
+#[command(no_binary_name = true)]
+struct MarkerArgs {
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+#[derive(Args, Debug)]
+struct DiffArgs {
+    /// Path to the older Python file, directory or archive.
+    old: PathBuf,
+
+    /// Path to the newer Python file, directory or archive.
+    new: PathBuf,
+
+    /// Format to report the differences in.
+    #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+    format: DiffFormat,
+
+    #[command(flatten)]
+    files: FileArgs,
+
+    #[command(flatten)]
+    classes: ClassArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DiffFormat {
+    Text,
+    Json,
+    Mermaid,
+}
+
+#[derive(Args, Debug)]
+struct FileArgs {
+    /// Space-delimited glob patterns of files to search, leaving out all others, e.g. `**/models/*.py`.
+    #[arg(long, value_delimiter = ' ', num_args = 1..)]
+    include_files: Vec<String>,
+
+    /// Space-delimited glob patterns of dotted module paths to search, leaving out all others, e.g. `pkg.models*`.
+    #[arg(long, value_delimiter = ' ', num_args = 1..)]
+    include_modules: Vec<String>,
 
     /// Space-delimited glob patterns to exclude directories from the search, e.g. `**/my_secret_dir/*`.
     #[arg(long, value_delimiter = ' ', num_args = 1..)]
@@ -27,52 +278,640 @@ struct Cli {
     /// Space-delimited glob patterns to exclude files from the search, e.g. `*secret*`.
     #[arg(long, value_delimiter = ' ', num_args = 1..)]
     exclude_files: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct FocusArgs {
+    /// Space-delimited glob patterns of classes to focus on, by name or dotted path, e.g. `Order`, drawing only them and the classes within reach of them.
+    #[arg(long, value_delimiter = ' ', num_args = 1..)]
+    focus: Vec<String>,
+
+    /// How many levels of parents of the classes in focus to draw, or all of them if unset.
+    #[arg(long, requires = "focus")]
+    ancestors: Option<usize>,
+
+    /// How many levels of children of the classes in focus to draw, or all of them if unset.
+    #[arg(long, requires = "focus")]
+    descendants: Option<usize>,
+
+    /// How many hops along fields and method signatures, in either direction, to draw from the classes in focus.
+    #[arg(long, requires = "focus", default_value_t = 1)]
+    related: usize,
+}
+
+#[derive(Args, Debug)]
+struct ClassArgs {
+    /// Space-delimited glob patterns of classes to draw, leaving out all others unless included by `--include-bases`, e.g. `Order*`.
+    #[arg(long, value_delimiter = ' ', num_args = 1..)]
+    include_classes: Vec<String>,
+
+    /// Space-delimited glob patterns of base classes to draw, along with their children at any depth, e.g. `Base`.
+    #[arg(long, value_delimiter = ' ', num_args = 1..)]
+    include_bases: Vec<String>,
 
     /// Space-delimited glob patterns to exclude classes from the diagram, e.g. `*Secret*`.
     #[arg(long, value_delimiter = ' ', num_args = 1..)]
     exclude_classes: Vec<String>,
 
-    /// Space-delimited glob patterns to exclude base classes and their children from the diagram, e.g. `*Base*`.
+    /// Space-delimited glob patterns to exclude base classes and their descendants from the diagram, e.g. `*Base*`.
     #[arg(long, value_delimiter = ' ', num_args = 1..)]
     exclude_bases: Vec<String>,
 }
 
-fn main() -> Result<()> {
-    let mut cfg = Cli::parse();
-    let paths = utils::get_file_paths(cfg.path, &cfg.exclude_dirs, &cfg.exclude_files)?;
+impl ClassArgs {
+    /// Checks every pattern up front, as filtering takes them to be valid.
+    fn validate(&self) -> Result<()> {
+        let patterns = [
+            &self.include_classes,
+            &self.include_bases,
+            &self.exclude_classes,
+            &self.exclude_bases,
+        ];
+        for p in patterns.into_iter().flatten() {
+            glob::Pattern::new(p).with_context(|| format!("invalid pattern `{p}`"))?;
+        }
+        Ok(())
+    }
+}
+
+type ParsedFile = (PathBuf, Vec<touml::PyClassInfo>);
 
-    let header = String::from("classDiagram\n\n");
-    let diagram = paths
-        .iter()
-        .filter_map(|p| match fs::read_to_string(p) {
-            Ok(c) => Some(c),
+/// Names the module a file under `root` would be imported as. A root that is
+/// itself a package is part of the name, e.g. `pkg.models` for `pkg/models.py`.
+fn module_of(root: &Path, path: &Path) -> String {
+    let relative = match path.strip_prefix(root) {
+        Ok(p) if p.as_os_str().is_empty() => Path::new(path.file_name().unwrap_or_default()),
+        Ok(p) => p,
+        Err(_) => path,
+    };
+    let relative = match root.file_name() {
+        Some(name) if root.join("__init__.py").is_file() => Path::new(name).join(relative),
+        _ => relative.to_path_buf(),
+    };
+    touml::module_name(&relative)
+}
+
+/// Reads every Python file found under `path`, along with its module name.
+fn read_sources(
+    path: &Path,
+    rev: Option<&str>,
+    files: &FileArgs,
+) -> Result<Vec<(PathBuf, String, String)>> {
+    let paths = utils::get_file_paths(path, rev, &files.exclude_dirs, &files.exclude_files)?;
+    let patterns = |globs: &[String]| {
+        globs
+            .iter()
+            .map(|g| glob::Pattern::new(g).with_context(|| format!("invalid pattern `{g}`")))
+            .collect::<Result<Vec<_>>>()
+    };
+    let (include_files, include_modules) = (
+        patterns(&files.include_files)?,
+        patterns(&files.include_modules)?,
+    );
+
+    Ok(paths
+        .par_iter()
+        .filter(|p| {
+            include_files.is_empty() || include_files.iter().any(|g| g.matches_path(p.path()))
+        })
+        .map(|p| (p, module_of(path, p.path())))
+        .filter(|(_, m)| include_modules.is_empty() || include_modules.iter().any(|g| g.matches(m)))
+        .filter_map(|(p, module)| match p.read() {
+            Ok(c) => Some((p.path().to_path_buf(), module, c)),
             Err(_) => {
-                eprintln!("Failed to load contents from file {}.", p.to_string_lossy());
+                eprintln!(
+                    "Failed to load contents from file {}.",
+                    p.path().to_string_lossy()
+                );
                 None
             }
         })
-        .par_bridge()
-        .map(|src| {
-            touml::python_to_mermaid(src, &cfg.exclude_classes, &cfg.exclude_bases)
+        .collect())
+}
+
+/// Parses every Python file found under `path`, returning the classes in each.
+fn load(path: &Path, rev: Option<&str>, files: &FileArgs) -> Result<Vec<ParsedFile>> {
+    read_sources(path, rev, files)?
+        .into_par_iter()
+        .map(|(path, module, src)| {
+            touml::parse_python(&src)
+                .map(|classes| {
+                    let classes = classes
+                        .into_iter()
+                        .map(|c| touml::PyClassInfo {
+                            module: module.clone(),
+                            path: path.clone(),
+                            ..c
+                        })
+                        .collect();
+                    (path, classes)
+                })
                 .map_err(|e| anyhow::anyhow!(e))
         })
-        .collect::<Result<Vec<_>>>()?
+        .collect()
+}
+
+/// Renders imports between the modules found under `path`.
+fn render_modules(path: &Path, cfg: &GenerateArgs, header: &config::Header) -> Result<String> {
+    let modules = read_sources(path, cfg.rev.as_deref(), &cfg.files)?
+        .into_par_iter()
+        .map(|(path, module, src)| {
+            let is_package = path.file_name().is_some_and(|n| n == "__init__.py");
+            touml::parse_imports(&src, &module, is_package)
+                .map(|imports| (module, imports))
+                .map_err(|e| anyhow::anyhow!(e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let header = header.print("flowchart");
+    let diagram = touml::modules_to_mermaid(&modules, cfg.package_depth).unwrap_or_default();
+    Ok(header + diagram.trim_end() + "\n")
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Diff(args)) => diff(*args).map(|_| ExitCode::SUCCESS),
+        Some(Command::Check(args)) => check(*args),
+        Some(Command::Embed(args)) => embed(args),
+        None => generate(cli.generate).map(|_| ExitCode::SUCCESS),
+    }
+}
+
+fn diff(args: DiffArgs) -> Result<()> {
+    args.classes.validate()?;
+    let exclude = |files: Vec<ParsedFile>| {
+        let classes = files.into_iter().flat_map(|(_, c)| c).collect();
+        let classes = touml::include_classes(
+            classes,
+            &args.classes.include_classes,
+            &args.classes.include_bases,
+        );
+        touml::exclude_classes(
+            classes,
+            &args.classes.exclude_classes,
+            &args.classes.exclude_bases,
+        )
+    };
+    let old = exclude(load(&args.old, None, &args.files)?);
+    let new = exclude(load(&args.new, None, &args.files)?);
+
+    let diff = touml::ModelDiff::new(old, new);
+    let output = match args.format {
+        DiffFormat::Text => diff.print(),
+        DiffFormat::Json => diff.to_json() + "\n",
+        DiffFormat::Mermaid => String::from("classDiagram\n\n") + &diff.to_mermaid(),
+    };
+    std::io::stdout().write_all(output.as_bytes())?;
+
+    Ok(())
+}
+
+fn source_path(cfg: &GenerateArgs) -> &Path {
+    cfg.path
+        .as_deref()
+        .expect("a path is required without a subcommand")
+}
+
+/// Renders the complete diagram described by the given arguments.
+fn render(cfg: &GenerateArgs, config: &config::Config) -> Result<String> {
+    if let Format::MermaidModules = cfg.format {
+        return render_modules(source_path(cfg), cfg, &config.header);
+    }
+    let (classes, classifier) = select(cfg)?;
+    render_classes(
+        cfg,
+        config,
+        &classes,
+        cfg.format,
+        &mermaid_options(cfg, config, classifier)?,
+    )
+}
+
+/// Keeps the classes matching `--focus`, along with those within reach.
+fn focus(classes: Vec<touml::PyClassInfo>, args: &FocusArgs) -> Result<Vec<touml::PyClassInfo>> {
+    let patterns = args
+        .focus
+        .iter()
+        .map(|p| glob::Pattern::new(p).with_context(|| format!("invalid pattern `{p}`")))
+        .collect::<Result<Vec<_>>>()?;
+    let seeds = classes
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| {
+            let qualified = c.qualified_name();
+            patterns
+                .iter()
+                .any(|p| p.matches(&c.name) || p.matches(&qualified))
+        })
+        .map(|(i, _)| i)
+        .collect::<BTreeSet<_>>();
+    if seeds.is_empty() {
+        anyhow::bail!("no class matches --focus {}", args.focus.join(" "));
+    }
+
+    let reach = touml::Reach {
+        ancestors: args.ancestors,
+        descendants: args.descendants,
+        related: args.related,
+    };
+    let keep = touml::focus(&classes, &seeds, reach);
+    Ok(classes
         .into_iter()
-        .flatten()
-        .collect::<Vec<_>>()
-        .join("\n\n");
-
-    if let Some(ref mut output) = cfg.output {
-        if output.is_dir() && output.exists() {
-            output.push(PathBuf::from(OUTPUT_FILENAME));
-            let mut file = File::create(output)?;
-            file.write_all((header + &diagram).as_bytes())?;
+        .enumerate()
+        .filter_map(|(i, c)| keep.contains(&i).then_some(c))
+        .collect())
+}
+
+/// Loads the classes to draw, as selected by the given arguments, along with
+/// a classifier of parents that knows of every class loaded.
+fn select(cfg: &GenerateArgs) -> Result<(Vec<touml::PyClassInfo>, touml::Classifier)> {
+    cfg.classes.validate()?;
+    let path = source_path(cfg);
+    let files = load(path, cfg.rev.as_deref(), &cfg.files)?;
+
+    let changes = cfg
+        .changed_since
+        .as_deref()
+        .map(|since| git::changed_lines(path, since, cfg.rev.as_deref()))
+        .transpose()?;
+
+    let mut classes = Vec::new();
+    let mut changed = BTreeSet::new();
+    for (path, file_classes) in files {
+        let ranges = changes.as_ref().and_then(|c| c.get(&path));
+        for cls in file_classes {
+            if ranges.is_some_and(|r| r.iter().any(|&(s, e)| cls.location.overlaps(s, e))) {
+                changed.insert(classes.len());
+            }
+            classes.push(cls);
+        }
+    }
+
+    let classifier = touml::Classifier::new(&classes);
+    if changes.is_some() {
+        let keep = touml::InheritanceGraph::new(&classes).neighbourhood(&changed);
+        classes = classes
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, c)| keep.contains(&i).then_some(c))
+            .collect();
+    }
+
+    classes = touml::include_classes(
+        classes,
+        &cfg.classes.include_classes,
+        &cfg.classes.include_bases,
+    );
+    if !cfg.focus.focus.is_empty() {
+        classes = focus(classes, &cfg.focus)?;
+    }
+
+    let classes = touml::exclude_classes(
+        classes,
+        &cfg.classes.exclude_classes,
+        &cfg.classes.exclude_bases,
+    );
+    Ok((classes, classifier))
+}
+
+/// Reads the config, combining the header options on the command line with
+/// those in it.
+fn config(cfg: &GenerateArgs) -> Result<config::Config> {
+    let config: config::Config = cfg
+        .config
+        .as_deref()
+        .map_or_else(|| Ok(Default::default()), config::Config::read)?;
+    Ok(config::Config {
+        header: cfg.header.clone().or(config.header),
+        ..config
+    })
+}
+
+/// Prepares the links to sources, with paths relative to the root of the
+/// repository if there is one, or else to the path given.
+fn links(cfg: &GenerateArgs) -> Result<Option<touml::LinkTemplate>> {
+    let Some(template) = &cfg.link_template else {
+        return Ok(None);
+    };
+    let path = source_path(cfg);
+    let rev = match template.contains("{rev}") {
+        true => git::commit(path, cfg.rev.as_deref().unwrap_or("HEAD"))?,
+        false => String::new(),
+    };
+    let root = match git::toplevel(path) {
+        Ok(root) => root,
+        Err(_) if path.is_dir() => std::path::absolute(path)?,
+        Err(_) => std::path::absolute(path.parent().unwrap_or(Path::new("")))?,
+    };
+    Ok(Some(touml::LinkTemplate {
+        template: template.clone(),
+        rev,
+        root,
+    }))
+}
+
+fn mermaid_options(
+    cfg: &GenerateArgs,
+    config: &config::Config,
+    classifier: touml::Classifier,
+) -> Result<touml::MermaidOptions> {
+    Ok(touml::MermaidOptions {
+        namespaces: cfg.namespaces,
+        package_depth: cfg.package_depth,
+        generics: cfg.generics,
+        links: links(cfg)?,
+        styles: config.styles.clone(),
+        externals: cfg.externals.into(),
+        elsewhere: BTreeMap::new(),
+        classifier: Some(classifier),
+    })
+}
+
+/// Renders a complete diagram of the given classes.
+fn render_classes(
+    cfg: &GenerateArgs,
+    config: &config::Config,
+    classes: &[touml::PyClassInfo],
+    format: Format,
+    options: &touml::MermaidOptions,
+) -> Result<String> {
+    match format {
+        Format::Mermaid => {
+            let header = config.header.print("classDiagram");
+            let diagram = touml::classes_to_mermaid(classes, options).unwrap_or_default();
+            Ok(header + diagram.trim_end() + "\n")
+        }
+        Format::MermaidEr => {
+            let header = config.header.print("erDiagram");
+            let diagram = touml::classes_to_mermaid_er(classes).unwrap_or_default();
+            Ok(header + diagram.trim_end() + "\n")
+        }
+        Format::MermaidModules => render_modules(source_path(cfg), cfg, &config.header),
+        Format::Plantuml => Ok(touml::classes_to_plantuml(classes)),
+        Format::Dot => Ok(touml::classes_to_dot(classes)),
+        Format::D2 => Ok(touml::classes_to_d2(classes)),
+        Format::Json => Ok(touml::classes_to_json(classes) + "\n"),
+        Format::Html => {
+            let title = match config.header.title.clone() {
+                Some(title) => title,
+                None => {
+                    let path = source_path(cfg).canonicalize()?;
+                    path.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into()
+                }
+            };
+            Ok(touml::classes_to_html(classes, &title))
+        }
+        Format::Svg => Ok(touml::classes_to_svg(classes)),
+        Format::Xmi => Ok(touml::classes_to_xmi(classes)),
+    }
+}
+
+/// Renders each target, loading the classes only once.
+fn render_targets(
+    cfg: &GenerateArgs,
+    config: &config::Config,
+    targets: &[Target],
+) -> Result<Vec<String>> {
+    let (classes, classifier) = match targets.iter().all(|t| t.format == Format::MermaidModules) {
+        true => Default::default(),
+        false => select(cfg)?,
+    };
+    let options = mermaid_options(cfg, config, classifier)?;
+    targets
+        .iter()
+        .map(|target| {
+            let diagram = render_classes(cfg, config, &classes, target.format, &options)?;
+            Ok(match target.fenced {
+                true => embed::Syntax::Markdown.wrap(&diagram),
+                false => diagram,
+            })
+        })
+        .collect()
+}
+
+/// Renders a diagram per group of classes into each output, taken as a
+/// directory, along with an index of them. An output with an extension, e.g.
+/// `docs/classes.md`, stands for the `docs/classes` directory of `.md` files.
+fn render_split(
+    cfg: &GenerateArgs,
+    config: &config::Config,
+    by: split::SplitBy,
+) -> Result<Vec<(PathBuf, String)>> {
+    if cfg.format == Format::MermaidModules {
+        anyhow::bail!("--split-by does not apply to --format mermaid-modules.");
+    }
+    let (classes, classifier) = select(cfg)?;
+    let groups = split::split(classes, by, cfg.package_depth);
+    let title = &config.header.title;
+    let options = mermaid_options(cfg, config, classifier)?;
+
+    let mut outputs = Vec::new();
+    for output in cfg.output.iter() {
+        let (dir, extension) = match output.extension() {
+            Some(e) if !output.is_dir() => {
+                (output.with_extension(""), e.to_string_lossy().into_owned())
+            }
+            _ => (output.clone(), cfg.format.extension().to_string()),
+        };
+        let files = groups
+            .iter()
+            .map(|(name, _)| format!("{name}.{extension}"))
+            .collect::<Vec<_>>();
+        let mut diagrams = Vec::new();
+        for (g, (name, classes)) in groups.iter().enumerate() {
+            let file = &files[g];
+            // Parents in other groups are stubbed, linking to their diagram.
+            let elsewhere = groups
+                .iter()
+                .zip(files.iter())
+                .enumerate()
+                .filter(|(h, _)| *h != g)
+                .flat_map(|(_, ((_, classes), file))| {
+                    classes.iter().map(|c| (c.qualified_name(), file.clone()))
+                })
+                .collect();
+            let options = touml::MermaidOptions {
+                elsewhere,
+                ..options.clone()
+            };
+            let target = Target::new(&dir.join(file), cfg.format);
+            let diagram = render_classes(cfg, config, classes, target.format, &options)?;
+            outputs.push((
+                target.path,
+                match target.fenced {
+                    true => embed::Syntax::Markdown.wrap(&diagram),
+                    false => diagram,
+                },
+            ));
+            diagrams.push((name.clone(), file.clone(), classes.len()));
+        }
+        outputs.push((
+            dir.join("index.md"),
+            split::index(title.as_deref(), &diagrams),
+        ));
+    }
+    Ok(outputs)
+}
+
+/// Renders every file to write to `--output`, along with its path.
+fn render_outputs(cfg: &GenerateArgs, config: &config::Config) -> Result<Vec<(PathBuf, String)>> {
+    if let Some(by) = cfg.split_by {
+        return render_split(cfg, config, by);
+    }
+    let targets = cfg
+        .output
+        .iter()
+        .map(|o| Target::new(o, cfg.format))
+        .collect::<Vec<_>>();
+    let diagrams = render_targets(cfg, config, &targets)?;
+    Ok(targets.into_iter().map(|t| t.path).zip(diagrams).collect())
+}
+
+fn generate(cfg: GenerateArgs) -> Result<()> {
+    let config = config(&cfg)?;
+    if cfg.output.is_empty() {
+        if cfg.split_by.is_some() {
+            anyhow::bail!("--output (-o) is required to split a diagram.");
+        }
+        let diagram = render(&cfg, &config)?;
+        std::io::stdout().write_all(diagram.as_bytes())?;
+        return Ok(());
+    }
+
+    for (path, diagram) in render_outputs(&cfg, &config)? {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, diagram)
+            .with_context(|| format!("failed to write {}", path.to_string_lossy()))?;
+    }
+
+    Ok(())
+}
+
+fn check(cfg: GenerateArgs) -> Result<ExitCode> {
+    if cfg.output.is_empty() {
+        anyhow::bail!("--output (-o) is required to check a diagram.");
+    }
+    let config = config(&cfg)?;
+
+    // Line endings and trailing whitespace are not worth failing a build over.
+    let normalize = |s: &str| s.replace("\r\n", "\n").trim_end().to_string() + "\n";
+    let mut stale = false;
+    for (path, expected) in render_outputs(&cfg, &config)? {
+        let actual = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.to_string_lossy()))?;
+        let (expected, actual) = (normalize(&expected), normalize(&actual));
+        if expected != actual {
+            stale = true;
+            print_diff(&path, &actual, &expected)?;
+        }
+    }
+
+    Ok(if stale {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn print_diff(path: &Path, old: &str, new: &str) -> Result<()> {
+    let name = path.to_string_lossy();
+    let diff = TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&name, &format!("{name} (regenerated)"))
+        .to_string();
+    std::io::stdout().write_all(diff.as_bytes())?;
+    Ok(())
+}
+
+fn embed(args: EmbedArgs) -> Result<ExitCode> {
+    let render_marker = |options: &str| {
+        let marker = MarkerArgs::try_parse_from(embed::marker_args(options))
+            .with_context(|| format!("invalid options in marker `{options}`"))?;
+        render(&marker.generate, &config(&marker.generate)?)
+    };
+
+    let mut stale = false;
+    for path in args.files.iter() {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.to_string_lossy()))?;
+        let updated = embed::update(&contents, embed::Syntax::from_path(path), render_marker)
+            .with_context(|| format!("failed to update {}", path.to_string_lossy()))?;
+        if updated == contents {
+            continue;
+        }
+
+        if args.check {
+            stale = true;
+            print_diff(path, &contents, &updated)?;
         } else {
-            anyhow::bail!("--output (-o) must be an existing directory path.");
+            fs::write(path, updated)?;
         }
+    }
+
+    Ok(if stale {
+        ExitCode::FAILURE
     } else {
-        std::io::stdout().write_all((header + &diagram).as_bytes())?;
+        ExitCode::SUCCESS
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flags() {
+        let cli = Cli::try_parse_from(["touml", "--hide-empty-members-box", "src"]).unwrap();
+        assert_eq!(cli.generate.header.hide_empty_members_box, Some(true));
+        assert_eq!(cli.generate.path.as_deref(), Some(Path::new("src")));
+
+        let cli = Cli::try_parse_from(["touml", "src"]).unwrap();
+        assert_eq!(cli.generate.header.hide_empty_members_box, None);
+        assert!(Cli::try_parse_from(["touml", "--hide-empty-members-box=false", "src"]).is_err());
+
+        let cli = Cli::try_parse_from(["touml", "src", "--include-classes", "Order["]).unwrap();
+        assert!(cli.generate.classes.validate().is_err());
     }
 
-    Ok(())
+    #[test]
+    fn test_target() {
+        let target = |path: &str, format| {
+            let t = Target::new(Path::new(path), format);
+            (t.format, t.fenced)
+        };
+        assert_eq!(
+            target("docs/classes.svg", Format::Mermaid),
+            (Format::Svg, false)
+        );
+        assert_eq!(
+            target("docs/classes.PUML", Format::Mermaid),
+            (Format::Plantuml, false)
+        );
+        assert_eq!(
+            target("docs/classes.md", Format::Mermaid),
+            (Format::Mermaid, true)
+        );
+        assert_eq!(
+            target("docs/models.md", Format::MermaidEr),
+            (Format::MermaidEr, true)
+        );
+        assert_eq!(
+            target("docs/models.mmd", Format::MermaidEr),
+            (Format::MermaidEr, false)
+        );
+        assert_eq!(
+            target("docs/models.mmd", Format::Dot),
+            (Format::Mermaid, false)
+        );
+        assert_eq!(target("docs/classes", Format::D2), (Format::D2, false));
+
+        let dir = std::env::temp_dir();
+        assert_eq!(Target::new(&dir, Format::Json).path, dir.join("out.json"));
+    }
 }
@@ -0,0 +1,170 @@
+use clap::ValueEnum;
+use std::collections::{BTreeMap, BTreeSet};
+use touml::{InheritanceGraph, PyClassInfo};
+
+/// Name of the group of classes without any parents or children in the model,
+/// when splitting by component.
+static STANDALONE: &str = "standalone";
+
+/// How to split a diagram into several.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SplitBy {
+    /// A diagram per module.
+    Module,
+    /// A diagram per package, i.e. the parent of each module, or the package at `--package-depth`.
+    Package,
+    /// A diagram per connected component of the inheritance graph.
+    Component,
+}
+
+/// Groups classes into named diagrams, in order of their names. Parents left
+/// in other groups are stubbed once the groups are rendered, linking to the
+/// diagram holding them.
+pub fn split(
+    classes: Vec<PyClassInfo>,
+    by: SplitBy,
+    depth: Option<usize>,
+) -> Vec<(String, Vec<PyClassInfo>)> {
+    let names = match by {
+        SplitBy::Module => classes.iter().map(|c| c.module.clone()).collect(),
+        SplitBy::Package => classes
+            .iter()
+            .map(|c| match depth {
+                Some(_) => touml::package_of(&c.module, depth),
+                None => c
+                    .module
+                    .rsplit_once('.')
+                    .map_or(c.module.clone(), |(p, _)| p.to_string()),
+            })
+            .collect(),
+        SplitBy::Component => components(&classes),
+    };
+
+    let mut groups: BTreeMap<String, Vec<PyClassInfo>> = BTreeMap::new();
+    for (name, cls) in names.into_iter().zip(classes) {
+        let name = match name.is_empty() {
+            true => "classes".to_string(),
+            false => name,
+        };
+        groups.entry(name).or_default().push(cls);
+    }
+    groups.into_iter().collect()
+}
+
+/// Names the connected component each class belongs to after its first root,
+/// e.g. `pkg.models.Base`, leaving classes on their own to a group of their own.
+fn components(classes: &[PyClassInfo]) -> Vec<String> {
+    let graph = InheritanceGraph::new(classes);
+    let mut names = vec![String::new(); classes.len()];
+    let mut seen = BTreeSet::new();
+    for start in 0..classes.len() {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut component = vec![start];
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            for &j in graph.parents_of(i).iter().chain(graph.children_of(i)) {
+                if seen.insert(j) {
+                    component.push(j);
+                    stack.push(j);
+                }
+            }
+        }
+        component.sort();
+
+        let name = match component.len() {
+            1 => STANDALONE.to_string(),
+            _ => {
+                let root = component
+                    .iter()
+                    .copied()
+                    .find(|&i| graph.parents_of(i).is_empty())
+                    .unwrap_or(start);
+                let cls = &classes[root];
+                match cls.module.is_empty() {
+                    true => cls.name.clone(),
+                    false => format!("{}.{}", cls.module, cls.name),
+                }
+            }
+        };
+        for i in component {
+            names[i] = name.clone();
+        }
+    }
+    names
+}
+
+/// Lists the diagrams in Markdown, linking each by its file name.
+pub fn index(title: Option<&str>, diagrams: &[(String, String, usize)]) -> String {
+    let mut result = format!("# {}\n\n", title.unwrap_or("Class diagrams"));
+    for (name, file, count) in diagrams {
+        let classes = match count {
+            1 => "class",
+            _ => "classes",
+        };
+        result.push_str(&format!("- [{name}](<{file}>): {count} {classes}\n"));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(name: &str, module: &str, parents: &[&str]) -> PyClassInfo {
+        PyClassInfo {
+            name: name.to_string(),
+            module: module.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let classes = vec![
+            class("Order", "pkg.models.order", &["Base"]),
+            class("Base", "pkg.models.base", &["pydantic.BaseModel"]),
+            class("Error", "pkg.errors", &["Exception"]),
+            class("Mixin", "pkg.models.base", &[]),
+            class("BaseModel", "pkg.schemas", &[]),
+        ];
+        let names = |by| {
+            split(classes.clone(), by, None)
+                .into_iter()
+                .map(|(name, classes)| (name, classes.into_iter().map(|c| c.name).collect()))
+                .collect::<Vec<(String, Vec<String>)>>()
+        };
+        let group = |name: &str, classes: &[&str]| {
+            (
+                name.to_string(),
+                classes.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            names(SplitBy::Module),
+            [
+                group("pkg.errors", &["Error"]),
+                group("pkg.models.base", &["Base", "Mixin"]),
+                group("pkg.models.order", &["Order"]),
+                group("pkg.schemas", &["BaseModel"]),
+            ]
+        );
+        assert_eq!(
+            names(SplitBy::Package),
+            [
+                group("pkg", &["Error", "BaseModel"]),
+                group("pkg.models", &["Order", "Base", "Mixin"]),
+            ]
+        );
+        assert_eq!(
+            names(SplitBy::Component),
+            [
+                group("pkg.models.base.Base", &["Order", "Base"]),
+                group("standalone", &["Error", "Mixin", "BaseModel"]),
+            ]
+        );
+    }
+}
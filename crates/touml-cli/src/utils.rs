@@ -1,43 +1,213 @@
+use crate::git;
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
 use glob::Pattern;
-use std::io;
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 static EXTENSIONS: [&str; 1] = ["py"];
+static ZIP_SUFFIXES: [&str; 2] = [".whl", ".zip"];
+static TAR_SUFFIXES: [&str; 2] = [".tar.gz", ".tgz"];
+
+/// A Python source file, either on disk or read into memory from an archive
+/// or a git revision.
+pub enum SourceFile {
+    Disk(PathBuf),
+    InMemory { path: PathBuf, contents: String },
+}
+
+impl SourceFile {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Disk(path) => path,
+            Self::InMemory { path, .. } => path,
+        }
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            Self::Disk(path) => fs::read_to_string(path),
+            Self::InMemory { contents, .. } => Ok(contents.clone()),
+        }
+    }
+}
+
+struct Excludes {
+    dirs: Vec<Pattern>,
+    files: Vec<Pattern>,
+}
+
+impl Excludes {
+    fn new(exclude_dirs: &[String], exclude_files: &[String]) -> Result<Self> {
+        let patterns = |globs: &[String]| {
+            globs
+                .iter()
+                .map(|g| Pattern::new(g).with_context(|| format!("invalid pattern `{g}`")))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            dirs: patterns(exclude_dirs)?,
+            files: patterns(exclude_files)?,
+        })
+    }
+
+    fn is_excluded_dir(&self, path: &Path) -> bool {
+        self.dirs.iter().any(|p| p.matches_path(path))
+    }
+
+    fn is_excluded_file(&self, path: &Path) -> bool {
+        self.files.iter().any(|p| p.matches_path(path))
+    }
+
+    /// Archive and revision members are not walked, so each of their parent
+    /// directories is checked against the directory patterns in turn.
+    fn is_excluded_member(&self, path: &Path) -> bool {
+        path.ancestors().any(|a| self.is_excluded_dir(a)) || self.is_excluded_file(path)
+    }
+}
+
+fn is_python_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext))
+}
+
+fn has_suffix(path: &Path, suffixes: &[&str]) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| suffixes.iter().any(|s| name.ends_with(s)))
+}
 
 pub fn get_file_paths(
-    root: PathBuf,
+    root: &Path,
+    rev: Option<&str>,
     exclude_dirs: &[String],
     exclude_files: &[String],
-) -> io::Result<Vec<PathBuf>> {
-    let dir_patterns = exclude_dirs
-        .iter()
-        .map(|d| Pattern::new(d).unwrap())
-        .collect::<Vec<_>>();
-    let file_patterns = exclude_files
-        .iter()
-        .map(|f| Pattern::new(f).unwrap())
-        .collect::<Vec<_>>();
+) -> Result<Vec<SourceFile>> {
+    let excludes = Excludes::new(exclude_dirs, exclude_files)?;
+
+    let mut paths = if let Some(rev) = rev {
+        read_rev(root, rev, &excludes)?
+    } else if root.is_file() && has_suffix(root, &ZIP_SUFFIXES) {
+        read_zip(root, &excludes)?
+    } else if root.is_file() && has_suffix(root, &TAR_SUFFIXES) {
+        read_tar_gz(root, &excludes)?
+    } else {
+        walk_dir(root, &excludes)?
+    };
+
+    // Sort so that output is stable regardless of filesystem or archive order.
+    paths.sort_by(|a, b| a.path().cmp(b.path()));
 
+    Ok(paths)
+}
+
+fn walk_dir(root: &Path, excludes: &Excludes) -> io::Result<Vec<SourceFile>> {
     let mut paths = Vec::new();
     for entry in WalkDir::new(root) {
         let entry = entry?;
         let path = entry.path();
 
-        if dir_patterns.iter().any(|p| p.matches_path(path)) {
+        if excludes.is_excluded_dir(path) {
             continue;
         } else if entry.file_type().is_file() {
-            if file_patterns.iter().any(|p| p.matches_path(path)) {
+            if excludes.is_excluded_file(path) {
                 continue;
             }
 
-            if let Some(ext) = path.extension() {
-                if EXTENSIONS.contains(&ext.to_str().unwrap()) {
-                    paths.push(path.to_path_buf());
-                }
+            if is_python_file(path) {
+                paths.push(SourceFile::Disk(path.to_path_buf()));
             }
         }
     }
 
     Ok(paths)
 }
+
+/// Reads Python files as of a git revision, rather than from the working tree.
+fn read_rev(root: &Path, rev: &str, excludes: &Excludes) -> Result<Vec<SourceFile>> {
+    let mut paths = Vec::new();
+    for (path, contents) in git::read_tree(root, rev)? {
+        if !is_python_file(&path) || excludes.is_excluded_member(&path) {
+            continue;
+        }
+
+        match String::from_utf8(contents) {
+            Ok(contents) => paths.push(SourceFile::InMemory { path, contents }),
+            Err(_) => eprintln!(
+                "Failed to load contents from file {}.",
+                path.to_string_lossy()
+            ),
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Reads Python files from a wheel or zip archive. Member paths are joined
+/// onto the archive path, so that exclude globs see the same shape of path
+/// as they would when walking an extracted copy.
+fn read_zip(root: &Path, excludes: &Excludes) -> io::Result<Vec<SourceFile>> {
+    let mut archive = zip::ZipArchive::new(File::open(root)?).map_err(io::Error::from)?;
+
+    let mut paths = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(io::Error::from)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+
+        let path = root.join(name);
+        if !is_python_file(&path) || excludes.is_excluded_member(&path) {
+            continue;
+        }
+
+        let mut contents = String::new();
+        if entry.read_to_string(&mut contents).is_err() {
+            eprintln!(
+                "Failed to load contents from file {}.",
+                path.to_string_lossy()
+            );
+            continue;
+        }
+        paths.push(SourceFile::InMemory { path, contents });
+    }
+
+    Ok(paths)
+}
+
+/// Reads Python files from a gzipped tarball, e.g., an sdist.
+fn read_tar_gz(root: &Path, excludes: &Excludes) -> io::Result<Vec<SourceFile>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(root)?));
+
+    let mut paths = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = root.join(entry.path()?);
+        if !is_python_file(&path) || excludes.is_excluded_member(&path) {
+            continue;
+        }
+
+        let mut contents = String::new();
+        if entry.read_to_string(&mut contents).is_err() {
+            eprintln!(
+                "Failed to load contents from file {}.",
+                path.to_string_lossy()
+            );
+            continue;
+        }
+        paths.push(SourceFile::InMemory { path, contents });
+    }
+
+    Ok(paths)
+}
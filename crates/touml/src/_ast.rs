@@ -9,9 +9,46 @@ fn handle_unexpected<T>(expr: ast::Expr) -> ParseResult<T> {
     Err(ParseError::UnexpectedExprType(expr))
 }
 
+/// Returns the docstring of a class or function body, if any, cleaned up the
+/// way `inspect.cleandoc` would.
+pub fn get_docstring(body: &[ast::Stmt]) -> Option<String> {
+    let Some(ast::Stmt::Expr(ast::StmtExpr { value, .. })) = body.first() else {
+        return None;
+    };
+    let ast::Expr::Constant(ast::ExprConstant {
+        value: ast::Constant::Str(doc),
+        ..
+    }) = value.as_ref()
+    else {
+        return None;
+    };
+
+    let mut lines = doc.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest = lines.collect::<Vec<_>>();
+    let indent = rest
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let cleaned = std::iter::once(first)
+        .chain(
+            rest.iter()
+                .map(|l| l.get(indent..).unwrap_or_default().trim_end()),
+        )
+        .collect::<Vec<_>>()
+        .join("\n");
+    let cleaned = cleaned.trim_matches('\n');
+    (!cleaned.is_empty()).then(|| cleaned.to_string())
+}
+
 pub trait PyExpr {
     fn print_value(&self) -> Option<String>;
     fn print_annotation(&self) -> ParseResult<String>;
+    fn print_decorator(&self) -> Option<String>;
+    fn print_expr(&self) -> Option<String>;
 }
 
 impl PyExpr for ast::Expr {
@@ -67,18 +104,18 @@ impl PyExpr for ast::Expr {
     fn print_annotation(&self) -> ParseResult<String> {
         match self {
             ast::Expr::Name(n) => Ok(n.id.to_string()),
-            ast::Expr::Constant(c) => match c.value {
+            ast::Expr::Constant(c) => match &c.value {
                 ast::Constant::Ellipsis => Ok("...".into()),
                 ast::Constant::None => Ok("None".into()),
+                // e.g., forward references, or the values in `Literal['a']`.
+                ast::Constant::Str(s) => Ok(format!("'{s}'")),
+                ast::Constant::Int(i) => Ok(i.to_string()),
+                ast::Constant::Bool(b) => Ok(if *b { "True" } else { "False" }.into()),
                 _ => handle_unexpected(self.clone())?,
             },
             ast::Expr::Subscript(s) => {
-                let t_outer = match s.value.as_ref() {
-                    ast::Expr::Name(t_outer) => t_outer.id.as_str().to_string(),
-                    _ => handle_unexpected(self.clone())?,
-                };
+                let t_outer = s.value.print_annotation()?;
                 let t_inner = match s.slice.as_ref() {
-                    ast::Expr::Name(n) => n.id.as_str().to_string(),
                     ast::Expr::Tuple(t) => {
                         let to_concat = t
                             .elts
@@ -87,10 +124,19 @@ impl PyExpr for ast::Expr {
                             .collect::<ParseResult<Vec<_>>>()?;
                         to_concat.join(", ")
                     }
-                    _ => handle_unexpected(self.clone())?,
+                    slice => slice.print_annotation()?,
                 };
                 Ok(format!("{}[{}]", t_outer, t_inner))
             }
+            // e.g., the arguments in `Callable[[int, str], None]`.
+            ast::Expr::List(l) => {
+                let elts = l
+                    .elts
+                    .iter()
+                    .map(|elt| elt.print_annotation())
+                    .collect::<ParseResult<Vec<_>>>()?;
+                Ok(format!("[{}]", elts.join(", ")))
+            }
             ast::Expr::BinOp(b) => {
                 let left = b.left.print_annotation()?;
                 let right = b.right.print_annotation()?;
@@ -104,6 +150,48 @@ impl PyExpr for ast::Expr {
             _ => handle_unexpected(self.clone())?,
         }
     }
+
+    /// Prints the name of a decorator, less any arguments passed to it,
+    /// e.g., `validator` for `@validator("name")`.
+    fn print_decorator(&self) -> Option<String> {
+        match self {
+            ast::Expr::Call(c) => c.func.print_decorator(),
+            _ => self.print_annotation().ok(),
+        }
+    }
+
+    /// Prints values, annotations and calls thereof, e.g., `String(50)`.
+    fn print_expr(&self) -> Option<String> {
+        match self {
+            ast::Expr::Call(c) => {
+                let call = Call::from(c);
+                let args = call
+                    .args
+                    .into_iter()
+                    .chain(call.keywords.into_iter().map(|(k, v)| format!("{k}={v}")))
+                    .collect::<Vec<_>>();
+                Some(format!("{}({})", call.func, args.join(", ")))
+            }
+            _ => self.print_value().or_else(|| self.print_annotation().ok()),
+        }
+    }
+}
+
+impl From<&ast::ExprCall> for Call {
+    fn from(value: &ast::ExprCall) -> Self {
+        let func = value.func.print_expr().unwrap_or_default();
+        let args = value.args.iter().filter_map(|a| a.print_expr()).collect();
+        let keywords = value
+            .keywords
+            .iter()
+            .filter_map(|k| Some((k.arg.as_ref()?.to_string(), k.value.print_expr()?)))
+            .collect();
+        Self {
+            func,
+            args,
+            keywords,
+        }
+    }
 }
 
 impl TryFrom<&ast::StmtAssign> for Field {
@@ -118,10 +206,7 @@ impl TryFrom<&ast::StmtAssign> for Field {
         let (dtype, default) = match value.value.as_ref() {
             // TODO: handle container types
             ast::Expr::Constant(c) => match &c.value {
-                ast::Constant::Str(s) => (
-                    Some(value.value.as_ref().print_annotation()?),
-                    Some(s.to_string()),
-                ),
+                ast::Constant::Str(s) => (Some("str".to_string()), Some(s.to_string())),
                 ast::Constant::Int(i) => (Some("int".to_string()), Some(i.to_string())),
                 ast::Constant::Bool(b) => (Some("bool".to_string()), Some(b.to_string())),
                 ast::Constant::None => (Some("None".to_string()), None),
@@ -205,10 +290,18 @@ macro_rules! pymethod_impl {
                         None
                     };
 
+                    let decorators = value
+                        .decorator_list
+                        .iter()
+                        .filter_map(|d| d.print_decorator())
+                        .collect();
+
                     Ok(Self {
                         name,
                         args,
                         returns,
+                        decorators,
+                        docstring: get_docstring(&value.body),
                     })
                 }
             }
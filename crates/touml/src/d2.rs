@@ -0,0 +1,273 @@
+use crate::prelude::*;
+use crate::python::*;
+use crate::relation::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+static INDENT: &str = "    ";
+
+/// Quotes keys and values that D2 would otherwise read as syntax, e.g., the
+/// `:` in `get(self, key: str)`.
+fn quote(text: &str) -> String {
+    let is_plain = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '+' | '-'));
+    match is_plain {
+        true => text.to_string(),
+        false => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+/// Quotes each segment of a dotted path, which D2 reads as nested keys.
+fn quote_path(path: &str) -> String {
+    path.split('.').map(quote).collect::<Vec<_>>().join(".")
+}
+
+fn edge(kind: RelationKind) -> (&'static str, &'static [&'static str]) {
+    match kind {
+        RelationKind::Inheritance => (
+            "->",
+            &[
+                "target-arrowhead.shape: triangle",
+                "target-arrowhead.style.filled: false",
+            ],
+        ),
+        RelationKind::Realization => (
+            "->",
+            &[
+                "target-arrowhead.shape: triangle",
+                "target-arrowhead.style.filled: false",
+                "style.stroke-dash: 3",
+            ],
+        ),
+        RelationKind::Composition => (
+            "--",
+            &[
+                "source-arrowhead.shape: diamond",
+                "source-arrowhead.style.filled: true",
+            ],
+        ),
+        RelationKind::Aggregation => (
+            "--",
+            &[
+                "source-arrowhead.shape: diamond",
+                "source-arrowhead.style.filled: false",
+            ],
+        ),
+        RelationKind::Association => ("->", &["target-arrowhead.shape: arrow"]),
+        RelationKind::Dependency => (
+            "->",
+            &["target-arrowhead.shape: arrow", "style.stroke-dash: 3"],
+        ),
+    }
+}
+
+fn make_class(cls: &PyClassInfo, indent: &str) -> String {
+    let inner = format!("{indent}{INDENT}");
+    let mut result = format!("{indent}{}: {{{EOL}", quote(&cls.name));
+    result.push_str(&format!("{inner}shape: class{EOL}"));
+    if !cls.type_params.is_empty() {
+        let label = format!("{}[{}]", cls.name, cls.type_params.join(", "));
+        result.push_str(&format!("{inner}label: {}{EOL}", quote(&label)));
+    }
+
+    let fields = cls.fields.iter().filter(|f| !f.is_dunder());
+    let methods = cls.methods.iter().filter(|m| !m.is_dunder());
+    let members = fields
+        .map(|f| {
+            let key = format!("{}{}", get_access_modifier(f.is_public()), f.name);
+            (key, f.dtype.clone())
+        })
+        .chain(methods.map(|m| {
+            let args = m
+                .args
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let key = format!("{}{}({args})", get_access_modifier(m.is_public()), m.name);
+            (key, m.returns.clone())
+        }))
+        .collect::<Vec<_>>();
+    if !members.is_empty() {
+        result.push_str(EOL);
+    }
+    for (key, value) in members {
+        match value {
+            Some(value) => {
+                result.push_str(&format!("{inner}{}: {}{EOL}", quote(&key), quote(&value)))
+            }
+            None => result.push_str(&format!("{inner}{}{EOL}", quote(&key))),
+        }
+    }
+
+    result.push_str(&format!("{indent}}}{EOL}"));
+    result
+}
+
+/// Modules nested by their dotted path, e.g., `models.base` within `models`.
+#[derive(Default)]
+struct Container<'a> {
+    classes: Vec<&'a PyClassInfo>,
+    children: BTreeMap<&'a str, Container<'a>>,
+}
+
+impl<'a> Container<'a> {
+    fn insert(&mut self, cls: &'a PyClassInfo) {
+        let mut container = self;
+        for part in cls.module.split('.').filter(|p| !p.is_empty()) {
+            container = container.children.entry(part).or_default();
+        }
+        container.classes.push(cls);
+    }
+
+    fn print(&self, indent: &str) -> String {
+        let mut blocks = self
+            .classes
+            .iter()
+            .map(|cls| make_class(cls, indent))
+            .collect::<Vec<_>>();
+        for (name, child) in self.children.iter() {
+            let mut block = format!("{indent}{}: {{{EOL}", quote(name));
+            block.push_str(&child.print(&format!("{indent}{INDENT}")));
+            block.push_str(&format!("{indent}}}{EOL}"));
+            blocks.push(block);
+        }
+        blocks.join(EOL)
+    }
+}
+
+/// Renders classes as a D2 diagram, with a container for each package and
+/// module, and every relationship between the classes as a connection.
+pub fn classes_to_d2(classes: &[PyClassInfo]) -> String {
+    // Parents above their children, as in the other diagrams.
+    let mut result = format!("direction: up{EOL}{EOL}");
+
+    let mut root = Container::default();
+    for cls in classes.iter() {
+        root.insert(cls);
+    }
+    result.push_str(&root.print(""));
+
+    let relations = relations(classes);
+    let externals = relations
+        .iter()
+        .filter_map(|r| match &r.to {
+            Target::External(name) => Some(name.as_str()),
+            Target::Class(_) => None,
+        })
+        .collect::<BTreeSet<_>>();
+    if !externals.is_empty() {
+        result.push_str(EOL);
+    }
+    for name in externals {
+        result.push_str(&format!("{}.style.stroke-dash: 3{EOL}", quote_path(name)));
+    }
+
+    for relation in relations {
+        let from = classes[relation.from].qualified_name();
+        let to = match relation.to {
+            Target::Class(j) => classes[j].qualified_name(),
+            Target::External(name) => name,
+        };
+        let (connection, attrs) = edge(relation.kind);
+        let label = relation
+            .label
+            .map(|l| format!(" {}", quote(&l)))
+            .unwrap_or_default();
+
+        result.push_str(EOL);
+        result.push_str(&format!(
+            "{} {connection} {}:{label} {{{EOL}",
+            quote_path(&from),
+            quote_path(&to)
+        ));
+        for attr in attrs {
+            result.push_str(&format!("{INDENT}{attr}{EOL}"));
+        }
+        result.push_str(&format!("}}{EOL}"));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_d2() {
+        let classes = [
+            PyClassInfo {
+                name: "Order".to_string(),
+                module: "shop.models".to_string(),
+                parents: BTreeSet::from(["pydantic.BaseModel".to_string()]),
+                fields: BTreeSet::from([
+                    Field {
+                        name: "_lines".to_string(),
+                        dtype: Some("list[Line]".to_string()),
+                        default: None,
+                    },
+                    Field {
+                        name: "note".to_string(),
+                        dtype: None,
+                        default: None,
+                    },
+                ]),
+                methods: BTreeSet::from([Method {
+                    name: "total".to_string(),
+                    args: vec![Field {
+                        name: "self".to_string(),
+                        dtype: None,
+                        default: None,
+                    }],
+                    returns: Some("int".to_string()),
+                    decorators: vec![],
+                    docstring: None,
+                }]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Line".to_string(),
+                module: "shop.models".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            "direction: up",
+            "",
+            "shop: {",
+            "    models: {",
+            "        Order: {",
+            "            shape: class",
+            "",
+            "            -_lines: \"list[Line]\"",
+            "            +note",
+            "            \"+total(self)\": int",
+            "        }",
+            "",
+            "        Line: {",
+            "            shape: class",
+            "        }",
+            "    }",
+            "}",
+            "",
+            "pydantic.BaseModel.style.stroke-dash: 3",
+            "",
+            "shop.models.Order -> pydantic.BaseModel: {",
+            "    target-arrowhead.shape: triangle",
+            "    target-arrowhead.style.filled: false",
+            "}",
+            "",
+            "shop.models.Order -- shop.models.Line: _lines {",
+            "    source-arrowhead.shape: diamond",
+            "    source-arrowhead.style.filled: false",
+            "}",
+            "",
+        ].join(EOL);
+        assert_eq!(classes_to_d2(&classes), expected);
+    }
+}
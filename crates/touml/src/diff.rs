@@ -0,0 +1,391 @@
+use crate::mermaid::{node_ids, MermaidAdapter};
+use crate::prelude::*;
+use crate::python::PyClassInfo;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+static INDENT: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Status {
+    fn symbol(&self) -> char {
+        match self {
+            Self::Added => '+',
+            Self::Removed => '-',
+            Self::Changed => '~',
+        }
+    }
+
+    fn css_class(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberKind {
+    Field,
+    Method,
+    Base,
+}
+
+impl MemberKind {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Field => "field",
+            Self::Method => "method",
+            Self::Base => "base",
+        }
+    }
+}
+
+/// A field, method or base class that differs between two versions of a class.
+/// `old` and `new` hold the rendered member on either side, where it exists.
+#[derive(Debug, Serialize)]
+pub struct MemberDiff {
+    pub kind: MemberKind,
+    pub name: String,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<String>,
+}
+
+impl MemberDiff {
+    fn print(&self) -> String {
+        let mut line = format!(
+            "{} {} {}",
+            self.status.symbol(),
+            self.kind.label(),
+            self.new
+                .as_ref()
+                .or(self.old.as_ref())
+                .unwrap_or(&self.name)
+        );
+        if let (Status::Changed, Some(old)) = (self.status, &self.old) {
+            line.push_str(&format!(" (was {old})"));
+        }
+        line
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClassDiff {
+    pub name: String,
+    pub status: Status,
+    pub members: Vec<MemberDiff>,
+
+    /// The newer version of the class, or the older one if it was removed.
+    #[serde(skip)]
+    class: PyClassInfo,
+}
+
+/// Classes added, removed or changed between two sets of extracted classes.
+///
+/// Classes are matched up by qualified name, e.g., `pkg.models.Base`, and
+/// members by name within their class.
+#[derive(Debug, Serialize)]
+pub struct ModelDiff {
+    pub classes: Vec<ClassDiff>,
+}
+
+/// Compares members as they are rendered, i.e., by their signature, so that
+/// a change to a docstring alone is not a change to the member.
+fn diff_members<T: Display>(
+    kind: MemberKind,
+    old: BTreeMap<&str, &T>,
+    new: BTreeMap<&str, &T>,
+) -> Vec<MemberDiff> {
+    let mut result = Vec::new();
+    for (name, o) in old.iter() {
+        match new.get(name) {
+            None => result.push(MemberDiff {
+                kind,
+                name: name.to_string(),
+                status: Status::Removed,
+                old: Some(o.to_string()),
+                new: None,
+            }),
+            Some(n) if n.to_string() != o.to_string() => result.push(MemberDiff {
+                kind,
+                name: name.to_string(),
+                status: Status::Changed,
+                old: Some(o.to_string()),
+                new: Some(n.to_string()),
+            }),
+            _ => continue,
+        }
+    }
+    for (name, n) in new.iter().filter(|(name, _)| !old.contains_key(*name)) {
+        result.push(MemberDiff {
+            kind,
+            name: name.to_string(),
+            status: Status::Added,
+            old: None,
+            new: Some(n.to_string()),
+        });
+    }
+    result
+}
+
+fn keyed<'a, T>(
+    items: impl IntoIterator<Item = &'a T>,
+    key: impl Fn(&'a T) -> &'a str,
+) -> BTreeMap<&'a str, &'a T> {
+    items.into_iter().map(|item| (key(item), item)).collect()
+}
+
+fn diff_classes(old: &PyClassInfo, new: &PyClassInfo) -> Vec<MemberDiff> {
+    let mut members = diff_members(
+        MemberKind::Field,
+        keyed(&old.fields, |f| &f.name),
+        keyed(&new.fields, |f| &f.name),
+    );
+    members.extend(diff_members(
+        MemberKind::Method,
+        keyed(&old.methods, |m| &m.name),
+        keyed(&new.methods, |m| &m.name),
+    ));
+    members.extend(diff_members(
+        MemberKind::Base,
+        keyed(&old.parents, |p| p),
+        keyed(&new.parents, |p| p),
+    ));
+    members
+}
+
+impl ModelDiff {
+    pub fn new(old: Vec<PyClassInfo>, new: Vec<PyClassInfo>) -> Self {
+        let mut old = old
+            .into_iter()
+            .map(|c| (c.qualified_name(), c))
+            .collect::<BTreeMap<_, _>>();
+        let new = new
+            .into_iter()
+            .map(|c| (c.qualified_name(), c))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut classes = Vec::new();
+        for (name, class) in new.into_iter() {
+            let (status, members) = match old.remove(&name) {
+                None => (Status::Added, Vec::new()),
+                Some(prev) => {
+                    let members = diff_classes(&prev, &class);
+                    if members.is_empty() {
+                        continue;
+                    }
+                    (Status::Changed, members)
+                }
+            };
+            classes.push(ClassDiff {
+                name,
+                status,
+                members,
+                class,
+            });
+        }
+        for (name, class) in old.into_iter() {
+            classes.push(ClassDiff {
+                name,
+                status: Status::Removed,
+                members: Vec::new(),
+                class,
+            });
+        }
+        classes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self { classes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub fn print(&self) -> String {
+        let mut result = String::new();
+        for class in self.classes.iter() {
+            result.push_str(&format!(
+                "{} class {}{EOL}",
+                class.status.symbol(),
+                class.name
+            ));
+            for member in class.members.iter() {
+                result.push_str(&format!("{INDENT}{}{EOL}", member.print()));
+            }
+        }
+        result
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("diffs serialize to JSON")
+    }
+
+    /// Renders the classes that differ as the body of a Mermaid class diagram,
+    /// colored by status, with a note on each changed class listing its changes.
+    pub fn to_mermaid(self) -> String {
+        let mut blocks = Vec::new();
+        let mut styled: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        let classes = self
+            .classes
+            .iter()
+            .map(|d| d.class.clone())
+            .collect::<Vec<_>>();
+        for (diff, id) in self.classes.into_iter().zip(node_ids(&classes)) {
+            styled
+                .entry(diff.status.css_class())
+                .or_default()
+                .push(id.clone());
+
+            let mut block = diff.class.to_mermaid().with_id(id.clone()).print();
+            if !diff.members.is_empty() {
+                let changes = diff
+                    .members
+                    .iter()
+                    .map(|m| m.print().replace('"', "#quot;"))
+                    .collect::<Vec<_>>()
+                    .join("\\n");
+                block.push_str(&format!("{EOL}{INDENT}note for {id} \"{changes}\"{EOL}"));
+            }
+            blocks.push(block);
+        }
+
+        let mut styles = [
+            "classDef added fill:#dcfce7,stroke:#16a34a",
+            "classDef removed fill:#fee2e2,stroke:#dc2626,stroke-dasharray:4",
+            "classDef changed fill:#fef9c3,stroke:#ca8a04",
+        ]
+        .iter()
+        .map(|s| format!("{INDENT}{s}"))
+        .collect::<Vec<_>>();
+        for (css_class, names) in styled.iter() {
+            styles.push(format!(
+                "{INDENT}cssClass \"{}\" {css_class}",
+                names.join(",")
+            ));
+        }
+        blocks.push(styles.join(EOL) + EOL);
+
+        blocks.join(EOL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn field(name: &str, dtype: &str) -> Field {
+        Field {
+            name: name.to_string(),
+            dtype: Some(dtype.to_string()),
+            default: None,
+        }
+    }
+
+    #[test]
+    fn test_model_diff() {
+        let old = vec![
+            PyClassInfo {
+                name: "Order".to_string(),
+                fields: BTreeSet::from([field("id", "int"), field("total", "int")]),
+                parents: BTreeSet::from(["Base".to_string()]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Legacy".to_string(),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Unchanged".to_string(),
+                ..Default::default()
+            },
+        ];
+        let new = vec![
+            PyClassInfo {
+                name: "Order".to_string(),
+                fields: BTreeSet::from([field("id", "str"), field("note", "str")]),
+                parents: BTreeSet::from(["BaseModel".to_string()]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Unchanged".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let diff = ModelDiff::new(old, new);
+        assert_eq!(
+            diff.print(),
+            [
+                "- class Legacy",
+                "~ class Order",
+                "    ~ field id: str (was id: int)",
+                "    - field total: int",
+                "    + field note: str",
+                "    - base Base",
+                "    + base BaseModel",
+                "",
+            ]
+            .join(EOL)
+        );
+    }
+
+    #[test]
+    fn test_model_diff_ignores_docstrings() {
+        let class = |docstring: &str| PyClassInfo {
+            name: "Order".to_string(),
+            methods: BTreeSet::from([Method {
+                name: "total".to_string(),
+                args: vec![field("self", "Order")],
+                returns: Some("int".to_string()),
+                decorators: Vec::new(),
+                docstring: Some(docstring.to_string()),
+            }]),
+            ..Default::default()
+        };
+
+        let diff = ModelDiff::new(vec![class("Old.")], vec![class("New.")]);
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_model_diff_qualified() {
+        let class = |name: &str, module: &str| PyClassInfo {
+            name: name.to_string(),
+            module: module.to_string(),
+            ..Default::default()
+        };
+        let old = vec![class("Base", "pkg.models"), class("Base", "pkg.views")];
+        let mut new = vec![class("Base", "pkg.models")];
+        new[0].fields.insert(field("id", "int"));
+
+        let diff = ModelDiff::new(old, new);
+        assert_eq!(
+            diff.print(),
+            [
+                "~ class pkg.models.Base",
+                "    + field id: int",
+                "- class pkg.views.Base",
+                "",
+            ]
+            .join(EOL)
+        );
+        let mermaid = diff.to_mermaid();
+        assert!(mermaid.contains("note for pkg_models_Base"));
+        assert!(mermaid.contains("cssClass \"pkg_views_Base\" removed"));
+    }
+}
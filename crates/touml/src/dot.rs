@@ -0,0 +1,258 @@
+use crate::prelude::*;
+use crate::python::*;
+use crate::relation::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+static INDENT: &str = "    ";
+
+/// Quotes an identifier, so that dotted names may be used as node IDs.
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn edge_attrs(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Inheritance => "arrowhead=empty",
+        RelationKind::Realization => "arrowhead=empty, style=dashed",
+        RelationKind::Composition => "dir=back, arrowtail=diamond",
+        RelationKind::Aggregation => "dir=back, arrowtail=odiamond",
+        RelationKind::Association => "arrowhead=vee",
+        RelationKind::Dependency => "arrowhead=vee, style=dashed",
+    }
+}
+
+fn make_field(field: &Field, kind: ClassKind) -> String {
+    if kind == ClassKind::Enum {
+        return escape_xml(&field.name);
+    }
+    let line = format!("{} {field}", get_access_modifier(field.is_public()));
+    match field.is_static() {
+        true => format!("<u>{}</u>", escape_xml(&line)),
+        false => escape_xml(&line),
+    }
+}
+
+fn make_method(method: &Method) -> String {
+    let line = escape_xml(&format!(
+        "{} {method}",
+        get_access_modifier(method.is_public())
+    ));
+    match (method.is_static(), method.is_abstract()) {
+        (true, _) => format!("<u>{line}</u>"),
+        (_, true) => format!("<i>{line}</i>"),
+        _ => line,
+    }
+}
+
+/// Renders a class as a UML box, with a compartment each for its name,
+/// fields and methods.
+fn make_node(cls: &PyClassInfo, indent: &str) -> String {
+    let kind = cls.kind();
+    let mut title = escape_xml(&cls.name);
+    if !cls.type_params.is_empty() {
+        title.push_str(&escape_xml(&format!("[{}]", cls.type_params.join(", "))));
+    }
+    title = match kind {
+        ClassKind::Class => format!("<b>{title}</b>"),
+        ClassKind::Abstract => format!("<b><i>{title}</i></b>"),
+        ClassKind::Interface => format!("«interface»<br/><b>{title}</b>"),
+        ClassKind::Enum => format!("«enumeration»<br/><b>{title}</b>"),
+    };
+
+    let mut rows = vec![title];
+    let fields = cls
+        .fields
+        .iter()
+        .filter(|f| !f.is_dunder())
+        .map(|f| make_field(f, kind) + "<br/>")
+        .collect::<String>();
+    if !fields.is_empty() {
+        rows.push(fields);
+    }
+    let methods = cls
+        .methods
+        .iter()
+        .filter(|m| !m.is_dunder())
+        .map(|m| make_method(m) + "<br/>")
+        .collect::<String>();
+    if !methods.is_empty() {
+        rows.push(methods);
+    }
+
+    let mut label =
+        String::from(r#"<table border="0" cellborder="1" cellspacing="0" cellpadding="4">"#);
+    for (i, row) in rows.iter().enumerate() {
+        match i {
+            0 => label.push_str(&format!("<tr><td>{row}</td></tr>")),
+            _ => label.push_str(&format!(
+                r#"<tr><td align="left" balign="left">{row}</td></tr>"#
+            )),
+        }
+    }
+    label.push_str("</table>");
+
+    format!(
+        "{indent}{} [label=<{label}>];{EOL}",
+        quote(&cls.qualified_name())
+    )
+}
+
+/// Names a cluster after its module, e.g., `"cluster_models.base"`, quoted
+/// rather than sanitized so that no two modules share a cluster.
+fn cluster_id(module: &str) -> String {
+    quote(&format!("cluster_{module}"))
+}
+
+/// Renders classes as a Graphviz digraph, with a cluster for each module.
+///
+/// Parents are ranked above their children, and every relationship between
+/// the classes becomes an edge.
+pub fn classes_to_dot(classes: &[PyClassInfo]) -> String {
+    let mut result = format!("digraph classes {{{EOL}");
+    result.push_str(&format!("{INDENT}rankdir=BT;{EOL}"));
+    result.push_str(&format!(
+        "{INDENT}node [shape=plain, fontname=\"Helvetica\"];{EOL}"
+    ));
+    result.push_str(&format!(
+        "{INDENT}edge [fontname=\"Helvetica\", fontsize=10];{EOL}"
+    ));
+
+    let mut modules: BTreeMap<&str, Vec<&PyClassInfo>> = BTreeMap::new();
+    for cls in classes.iter() {
+        modules.entry(cls.module.as_str()).or_default().push(cls);
+    }
+    for (module, classes) in modules {
+        result.push_str(EOL);
+        if module.is_empty() {
+            for cls in classes {
+                result.push_str(&make_node(cls, INDENT));
+            }
+            continue;
+        }
+
+        result.push_str(&format!("{INDENT}subgraph {} {{{EOL}", cluster_id(module)));
+        result.push_str(&format!("{INDENT}{INDENT}label={};{EOL}", quote(module)));
+        for cls in classes {
+            result.push_str(&make_node(cls, &INDENT.repeat(2)));
+        }
+        result.push_str(&format!("{INDENT}}}{EOL}"));
+    }
+
+    let relations = relations(classes);
+    let externals = relations
+        .iter()
+        .filter_map(|r| match &r.to {
+            Target::External(name) => Some(name.as_str()),
+            Target::Class(_) => None,
+        })
+        .collect::<BTreeSet<_>>();
+    if !externals.is_empty() {
+        result.push_str(EOL);
+    }
+    for name in externals {
+        result.push_str(&format!(
+            "{INDENT}{} [shape=box, style=dashed, label={}];{EOL}",
+            quote(name),
+            quote(name)
+        ));
+    }
+
+    if !relations.is_empty() {
+        result.push_str(EOL);
+    }
+    for relation in relations {
+        let from = classes[relation.from].qualified_name();
+        let to = match relation.to {
+            Target::Class(j) => classes[j].qualified_name(),
+            Target::External(name) => name,
+        };
+        let mut attrs = edge_attrs(relation.kind).to_string();
+        if let Some(label) = relation.label {
+            attrs.push_str(&format!(", label={}", quote(&label)));
+        }
+        result.push_str(&format!(
+            "{INDENT}{} -> {} [{attrs}];{EOL}",
+            quote(&from),
+            quote(&to)
+        ));
+    }
+
+    result.push_str(&format!("}}{EOL}"));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot() {
+        let classes = [
+            PyClassInfo {
+                name: "Base".to_string(),
+                module: "models".to_string(),
+                parents: BTreeSet::from(["pydantic.BaseModel".to_string()]),
+                fields: BTreeSet::from([Field {
+                    name: "id".to_string(),
+                    dtype: Some("dict[str, int]".to_string()),
+                    default: None,
+                }]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Order".to_string(),
+                module: "models".to_string(),
+                parents: BTreeSet::from(["Base".to_string()]),
+                methods: BTreeSet::from([Method {
+                    name: "create".to_string(),
+                    args: vec![],
+                    returns: Some("Order".to_string()),
+                    decorators: vec!["classmethod".to_string()],
+                    docstring: None,
+                }]),
+                ..Default::default()
+            },
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            "digraph classes {",
+            "    rankdir=BT;",
+            "    node [shape=plain, fontname=\"Helvetica\"];",
+            "    edge [fontname=\"Helvetica\", fontsize=10];",
+            "",
+            "    subgraph \"cluster_models\" {",
+            "        label=\"models\";",
+            concat!(
+                "        \"models.Base\" [label=<",
+                "<table border=\"0\" cellborder=\"1\" cellspacing=\"0\" cellpadding=\"4\">",
+                "<tr><td><b>Base</b></td></tr>",
+                "<tr><td align=\"left\" balign=\"left\">+ id: dict[str, int]<br/></td></tr>",
+                "</table>>];",
+            ),
+            concat!(
+                "        \"models.Order\" [label=<",
+                "<table border=\"0\" cellborder=\"1\" cellspacing=\"0\" cellpadding=\"4\">",
+                "<tr><td><b>Order</b></td></tr>",
+                "<tr><td align=\"left\" balign=\"left\"><u>+ create() -&gt; Order</u><br/></td></tr>",
+                "</table>>];",
+            ),
+            "    }",
+            "",
+            "    \"pydantic.BaseModel\" [shape=box, style=dashed, label=\"pydantic.BaseModel\"];",
+            "",
+            "    \"models.Base\" -> \"pydantic.BaseModel\" [arrowhead=empty];",
+            "    \"models.Order\" -> \"models.Base\" [arrowhead=empty];",
+            "}",
+            "",
+        ].join(EOL);
+        assert_eq!(classes_to_dot(&classes), expected);
+    }
+
+    #[test]
+    fn test_cluster_id() {
+        assert_eq!(cluster_id("pkg.a_b"), "\"cluster_pkg.a_b\"");
+        assert_ne!(cluster_id("pkg.a_b"), cluster_id("pkg_a.b"));
+    }
+}
@@ -0,0 +1,480 @@
+use crate::prelude::*;
+use crate::python::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+static INDENT: &str = "    ";
+
+/// The ORMs whose models can be drawn as entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Orm {
+    SqlAlchemy,
+    SqlModel,
+    Django,
+}
+
+struct Column {
+    dtype: String,
+    name: String,
+    is_primary_key: bool,
+
+    /// The table or class referenced, if a foreign key.
+    references: Option<String>,
+}
+
+struct Relationship {
+    /// The entity at the "one" end, or either end of a many-to-many.
+    one: String,
+    many: String,
+    cardinality: &'static str,
+    label: String,
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(value, "True" | "true")
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '\'' || c == '"')
+}
+
+fn last_segment(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// Splits `Outer[inner]` into its parts.
+fn split_subscript(dtype: &str) -> Option<(&str, &str)> {
+    let (outer, inner) = dtype.split_once('[')?;
+    Some((outer, inner.strip_suffix(']')?))
+}
+
+/// Strips `Mapped[...]`, `Optional[...]` and `| None` from an annotation,
+/// returning what is left and whether the column may be null.
+fn unwrap_annotation(dtype: &str) -> (&str, bool) {
+    let mut dtype = dtype.trim();
+    let mut nullable = false;
+    loop {
+        if let Some(inner) = dtype
+            .strip_suffix("| None")
+            .or_else(|| dtype.strip_prefix("None |"))
+        {
+            dtype = inner.trim();
+            nullable = true;
+            continue;
+        }
+        match split_subscript(dtype) {
+            Some((outer, inner)) if last_segment(outer) == "Mapped" => dtype = inner.trim(),
+            Some((outer, inner)) if last_segment(outer) == "Optional" => {
+                dtype = inner.trim();
+                nullable = true;
+            }
+            _ => return (dtype, nullable),
+        }
+    }
+}
+
+/// Reduces a type to a name Mermaid accepts as an attribute type, e.g.,
+/// `String` for `sa.String(50)` and `datetime` for `datetime.datetime`.
+fn type_name(dtype: &str) -> String {
+    let head = dtype.split(['[', '(']).next().unwrap_or(dtype);
+    let name = last_segment(head.trim())
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    match name.is_empty() {
+        true => "unknown".to_string(),
+        false => name,
+    }
+}
+
+/// Returns the table referenced by an argument like `ForeignKey('users.id')`.
+fn foreign_key_table(arg: &str) -> Option<&str> {
+    let (func, rest) = arg.split_once('(')?;
+    if last_segment(func) != "ForeignKey" {
+        return None;
+    }
+    let target = unquote(rest.split([',', ')']).next()?.trim());
+    Some(target.split('.').next().unwrap_or(target))
+}
+
+fn detect_orm(cls: &PyClassInfo) -> Option<Orm> {
+    if cls.keywords.get("table").is_some_and(|t| is_true(t)) {
+        return Some(Orm::SqlModel);
+    }
+    if cls.parents.iter().any(|p| p == "models.Model")
+        || cls.calls.values().any(|c| c.func.starts_with("models."))
+    {
+        return Some(Orm::Django);
+    }
+
+    let is_abstract = cls
+        .fields
+        .iter()
+        .any(|f| f.name == "__abstract__" && f.default.as_deref().is_some_and(is_true));
+    let has_table = cls.fields.iter().any(|f| f.name == "__tablename__");
+    let has_columns = cls.fields.iter().any(|f| {
+        f.dtype
+            .as_deref()
+            .and_then(split_subscript)
+            .is_some_and(|(outer, _)| last_segment(outer) == "Mapped")
+    }) || cls
+        .calls
+        .values()
+        .any(|c| matches!(c.name(), "mapped_column" | "Column" | "relationship"));
+    (has_table && has_columns && !is_abstract).then_some(Orm::SqlAlchemy)
+}
+
+/// A class detected as an ORM model, with the name of its table.
+struct Entity<'a> {
+    cls: &'a PyClassInfo,
+    orm: Orm,
+    table: String,
+}
+
+impl<'a> Entity<'a> {
+    fn new(cls: &'a PyClassInfo) -> Option<Self> {
+        let orm = detect_orm(cls)?;
+        let tablename = cls
+            .fields
+            .iter()
+            .find(|f| f.name == "__tablename__")
+            .and_then(|f| f.default.clone());
+        let table = match orm {
+            Orm::SqlAlchemy | Orm::SqlModel => tablename.unwrap_or_else(|| cls.name.to_lowercase()),
+            Orm::Django => cls.name.clone(),
+        };
+        Some(Self { cls, orm, table })
+    }
+
+    /// Gathers columns, along with relationships declared without one, i.e.,
+    /// many-to-many relationships.
+    fn columns(&self) -> (Vec<Column>, Vec<Relationship>) {
+        match self.orm {
+            Orm::SqlAlchemy | Orm::SqlModel => self.sqlalchemy_columns(),
+            Orm::Django => self.django_columns(),
+        }
+    }
+
+    fn sqlalchemy_columns(&self) -> (Vec<Column>, Vec<Relationship>) {
+        let mut columns = Vec::new();
+        let mut relationships = Vec::new();
+        for field in self.cls.fields.iter().filter(|f| !f.is_dunder()) {
+            let call = self.cls.calls.get(&field.name);
+            let dtype = field.dtype.as_deref().map(unwrap_annotation);
+
+            match call.map(|c| c.name()) {
+                Some("relationship" | "Relationship") => {
+                    let call = call.unwrap();
+                    if call.keywords.contains_key("secondary") {
+                        // Either `relationship('Tag', ...)` or `Mapped[list['Tag']]`.
+                        let target = call.args.first().map(|a| unquote(a)).or_else(|| {
+                            let (d, _) = dtype?;
+                            split_subscript(d).map(|(_, inner)| unquote(inner))
+                        });
+                        let target = target.map(type_name);
+                        if let Some(target) = target {
+                            relationships.push(Relationship {
+                                one: self.cls.name.clone(),
+                                many: target,
+                                cardinality: "}o--o{",
+                                label: field.name.clone(),
+                            });
+                        }
+                    }
+                    continue;
+                }
+                Some("mapped_column" | "Column" | "Field") => {}
+                _ if self.orm == Orm::SqlModel && dtype.is_some() => {}
+                _ if field
+                    .dtype
+                    .as_deref()
+                    .and_then(split_subscript)
+                    .is_some_and(|(outer, _)| last_segment(outer) == "Mapped") => {}
+                _ => continue,
+            }
+            if field.is_static() {
+                continue;
+            }
+
+            let args = call.map(|c| c.args.as_slice()).unwrap_or_default();
+            let keyword = |k: &str| call.and_then(|c| c.keywords.get(k));
+            let references = args
+                .iter()
+                .find_map(|a| foreign_key_table(a))
+                .map(str::to_string)
+                .or_else(|| {
+                    keyword("foreign_key").map(|fk| {
+                        let fk = unquote(fk);
+                        fk.split('.').next().unwrap_or(fk).to_string()
+                    })
+                });
+            let declared = args
+                .iter()
+                .find(|a| !a.starts_with(['\'', '"']) && foreign_key_table(a).is_none());
+            let dtype = match (dtype, declared) {
+                (Some((d, _)), _) => type_name(d),
+                (None, Some(d)) => type_name(d),
+                (None, None) => "unknown".to_string(),
+            };
+
+            columns.push(Column {
+                dtype,
+                name: field.name.clone(),
+                is_primary_key: keyword("primary_key").is_some_and(|v| is_true(v)),
+                references,
+            });
+        }
+        (columns, relationships)
+    }
+
+    fn django_columns(&self) -> (Vec<Column>, Vec<Relationship>) {
+        let mut columns = Vec::new();
+        let mut relationships = Vec::new();
+        for (name, call) in self.cls.calls.iter() {
+            if !call.name().ends_with("Field") && call.name() != "ForeignKey" {
+                continue;
+            }
+            let target = || {
+                call.args
+                    .first()
+                    .or_else(|| call.keywords.get("to"))
+                    .map(|t| match last_segment(unquote(t)) {
+                        "self" => self.cls.name.clone(),
+                        t => t.to_string(),
+                    })
+            };
+
+            match call.name() {
+                "ManyToManyField" => {
+                    if let Some(target) = target() {
+                        relationships.push(Relationship {
+                            one: self.cls.name.clone(),
+                            many: target,
+                            cardinality: "}o--o{",
+                            label: name.clone(),
+                        });
+                    }
+                }
+                kind => columns.push(Column {
+                    dtype: kind.to_string(),
+                    name: name.clone(),
+                    is_primary_key: call.keywords.get("primary_key").is_some_and(|v| is_true(v)),
+                    references: matches!(kind, "ForeignKey" | "OneToOneField")
+                        .then(target)
+                        .flatten(),
+                }),
+            }
+        }
+
+        // Django adds an `id` unless told otherwise.
+        if !columns.iter().any(|c| c.is_primary_key) {
+            columns.insert(
+                0,
+                Column {
+                    dtype: "BigAutoField".to_string(),
+                    name: "id".to_string(),
+                    is_primary_key: true,
+                    references: None,
+                },
+            );
+        }
+        (columns, relationships)
+    }
+
+    /// The cardinality of the relationship a foreign key column implies.
+    fn cardinality(&self, column: &Column) -> &'static str {
+        let (nullable, unique) = match self.orm {
+            Orm::Django => {
+                let call = &self.cls.calls[&column.name];
+                let null = call.keywords.get("null").is_some_and(|v| is_true(v));
+                (null, call.name() == "OneToOneField")
+            }
+            Orm::SqlAlchemy | Orm::SqlModel => {
+                let call = self.cls.calls.get(&column.name);
+                let keyword = |k| call.and_then(|c| c.keywords.get(k));
+                let annotated = self
+                    .cls
+                    .fields
+                    .iter()
+                    .find(|f| f.name == column.name)
+                    .and_then(|f| f.dtype.as_deref())
+                    .is_some_and(|d| unwrap_annotation(d).1);
+                (
+                    annotated || keyword("nullable").is_some_and(|v| is_true(v)),
+                    keyword("unique").is_some_and(|v| is_true(v)),
+                )
+            }
+        };
+        match (nullable, unique) {
+            (false, false) => "||--o{",
+            (true, false) => "|o--o{",
+            (false, true) => "||--o|",
+            (true, true) => "|o--o|",
+        }
+    }
+}
+
+/// Renders ORM models as the body of a Mermaid entity relationship diagram.
+///
+/// SQLAlchemy declarative models, SQLModel tables and Django models become
+/// entities; other classes are left out.
+pub fn classes_to_mermaid_er(classes: &[PyClassInfo]) -> Option<String> {
+    let entities = classes.iter().filter_map(Entity::new).collect::<Vec<_>>();
+    let by_table = entities
+        .iter()
+        .map(|e| (e.table.as_str(), e.cls.name.as_str()))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut blocks = Vec::new();
+    let mut relationships = Vec::new();
+    for entity in entities.iter() {
+        let (columns, many_to_many) = entity.columns();
+        relationships.extend(many_to_many);
+
+        let mut block = format!("{INDENT}{} {{{EOL}", entity.cls.name);
+        for column in columns.iter() {
+            let keys = [
+                column.is_primary_key.then_some("PK"),
+                column.references.is_some().then_some("FK"),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+            block.push_str(&format!("{INDENT}{INDENT}{} {}", column.dtype, column.name));
+            if !keys.is_empty() {
+                block.push_str(&format!(" {}", keys.join(", ")));
+            }
+            block.push_str(EOL);
+
+            if let Some(references) = &column.references {
+                // Django references classes, and the others tables.
+                let one = match entity.orm {
+                    Orm::Django => references.clone(),
+                    _ => by_table
+                        .get(references.as_str())
+                        .map_or_else(|| references.clone(), |n| n.to_string()),
+                };
+                relationships.push(Relationship {
+                    one,
+                    many: entity.cls.name.clone(),
+                    cardinality: entity.cardinality(column),
+                    label: column.name.clone(),
+                });
+            }
+        }
+        block.push_str(&format!("{INDENT}}}{EOL}"));
+        blocks.push(block);
+    }
+
+    if blocks.is_empty() {
+        return None;
+    }
+
+    // Both sides of a many-to-many may declare it.
+    let mut seen = BTreeSet::new();
+    let lines = relationships
+        .into_iter()
+        .filter(|r| {
+            let pair = if r.one < r.many {
+                (r.one.clone(), r.many.clone())
+            } else {
+                (r.many.clone(), r.one.clone())
+            };
+            r.cardinality != "}o--o{" || seen.insert(pair)
+        })
+        .map(|r| {
+            format!(
+                "{INDENT}{} {} {} : \"{}\"{EOL}",
+                r.one, r.cardinality, r.many, r.label
+            )
+        })
+        .collect::<String>();
+    if !lines.is_empty() {
+        blocks.push(lines);
+    }
+
+    Some(blocks.join(EOL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_python;
+
+    #[test]
+    fn test_sqlalchemy() {
+        #[rustfmt::skip]
+        let py = [
+            "class Base(DeclarativeBase):",
+            "    pass",
+            "",
+            "class User(Base):",
+            "    __tablename__ = 'user_account'",
+            "    id: Mapped[int] = mapped_column(primary_key=True)",
+            "    name: Mapped[Optional[str]] = mapped_column(String(30))",
+            "    addresses: Mapped[list['Address']] = relationship(back_populates='user')",
+            "",
+            "class Address(Base):",
+            "    __tablename__ = 'address'",
+            "    id = Column(Integer, primary_key=True)",
+            "    user_id: Mapped[int | None] = mapped_column(ForeignKey('user_account.id'))",
+        ].join("\n");
+
+        #[rustfmt::skip]
+        let expected = [
+            "    User {",
+            "        int id PK",
+            "        str name",
+            "    }",
+            "",
+            "    Address {",
+            "        Integer id PK",
+            "        int user_id FK",
+            "    }",
+            "",
+            "    User |o--o{ Address : \"user_id\"",
+            "",
+        ].join(EOL);
+
+        let classes = parse_python(&py).unwrap();
+        assert_eq!(classes_to_mermaid_er(&classes).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_django_and_sqlmodel() {
+        #[rustfmt::skip]
+        let py = [
+            "class Post(models.Model):",
+            "    title = models.CharField(max_length=200)",
+            "    author = models.ForeignKey('auth.User', on_delete=models.CASCADE)",
+            "    tags = models.ManyToManyField(Tag)",
+            "",
+            "class Hero(SQLModel, table=True):",
+            "    id: Optional[int] = Field(default=None, primary_key=True)",
+            "    team_id: int = Field(foreign_key='team.id', unique=True)",
+        ].join("\n");
+
+        #[rustfmt::skip]
+        let expected = [
+            "    Post {",
+            "        BigAutoField id PK",
+            "        ForeignKey author FK",
+            "        CharField title",
+            "    }",
+            "",
+            "    Hero {",
+            "        int id PK",
+            "        int team_id FK",
+            "    }",
+            "",
+            "    Post }o--o{ Tag : \"tags\"",
+            "    User ||--o{ Post : \"author\"",
+            "    team ||--o| Hero : \"team_id\"",
+            "",
+        ].join(EOL);
+
+        let classes = parse_python(&py).unwrap();
+        assert_eq!(classes_to_mermaid_er(&classes).unwrap(), expected);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::python::PyClassInfo;
+use crate::relation::{relations, RelationKind, Target};
+
+use std::collections::BTreeSet;
+
+/// Inheritance edges between classes gathered from any number of sources.
+///
+/// Parents are resolved as in [`relations`], so `pydantic.BaseModel` does not
+/// link to a class of the model named `BaseModel`. Classes are referred to by
+/// their index in the slice the graph was built from.
+pub struct InheritanceGraph {
+    parents: Vec<BTreeSet<usize>>,
+    children: Vec<BTreeSet<usize>>,
+}
+
+impl InheritanceGraph {
+    pub fn new(classes: &[PyClassInfo]) -> Self {
+        let mut parents = vec![BTreeSet::new(); classes.len()];
+        let mut children = vec![BTreeSet::new(); classes.len()];
+        for relation in relations(classes) {
+            let Target::Class(to) = relation.to else {
+                continue;
+            };
+            if let RelationKind::Inheritance | RelationKind::Realization = relation.kind {
+                parents[relation.from].insert(to);
+                children[to].insert(relation.from);
+            }
+        }
+
+        Self { parents, children }
+    }
+
+    pub fn parents_of(&self, i: usize) -> &BTreeSet<usize> {
+        &self.parents[i]
+    }
+
+    pub fn children_of(&self, i: usize) -> &BTreeSet<usize> {
+        &self.children[i]
+    }
+
+    /// Returns the given classes along with their direct parents and children.
+    pub fn neighbourhood(&self, seeds: &BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut result = seeds.clone();
+        for &i in seeds.iter() {
+            result.extend(self.parents_of(i));
+            result.extend(self.children_of(i));
+        }
+        result
+    }
+}
+
+/// How far to reach out from the classes in focus, in hops along edges.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reach {
+    /// Hops up the inheritance graph, or all the way if unset.
+    pub ancestors: Option<usize>,
+    /// Hops down the inheritance graph, or all the way if unset.
+    pub descendants: Option<usize>,
+    /// Hops along any other relationship, in either direction.
+    pub related: usize,
+}
+
+/// Visits the classes within `hops` of the seeds, given each class's
+/// neighbours.
+fn within(seeds: &BTreeSet<usize>, hops: Option<usize>, edges: &[Vec<usize>]) -> BTreeSet<usize> {
+    let mut result = seeds.clone();
+    let mut frontier = seeds.iter().copied().collect::<Vec<_>>();
+    let mut hop = 0;
+    while !frontier.is_empty() && hops.is_none_or(|h| hop < h) {
+        frontier = frontier
+            .into_iter()
+            .flat_map(|i| edges[i].iter().copied())
+            .filter(|&j| result.insert(j))
+            .collect();
+        hop += 1;
+    }
+    result
+}
+
+/// Returns the seeds along with the classes within reach of them, following
+/// every relationship between classes in the model, across modules.
+pub fn focus(classes: &[PyClassInfo], seeds: &BTreeSet<usize>, reach: Reach) -> BTreeSet<usize> {
+    let mut parents = vec![Vec::new(); classes.len()];
+    let mut children = vec![Vec::new(); classes.len()];
+    let mut related = vec![Vec::new(); classes.len()];
+    for relation in relations(classes) {
+        let Target::Class(to) = relation.to else {
+            continue;
+        };
+        match relation.kind {
+            RelationKind::Inheritance | RelationKind::Realization => {
+                parents[relation.from].push(to);
+                children[to].push(relation.from);
+            }
+            _ => {
+                related[relation.from].push(to);
+                related[to].push(relation.from);
+            }
+        }
+    }
+
+    let mut result = within(seeds, reach.ancestors, &parents);
+    result.extend(within(seeds, reach.descendants, &children));
+    result.extend(within(seeds, Some(reach.related), &related));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Field;
+
+    fn class(name: &str, parents: &[&str]) -> PyClassInfo {
+        PyClassInfo {
+            name: name.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_neighbourhood() {
+        let classes = [
+            class("Base", &["pydantic.BaseModel"]),
+            class("Order", &["models.Base"]),
+            class("SpecialOrder", &["Order"]),
+            class("Unrelated", &[]),
+            class("BaseModel", &[]),
+        ]
+        .map(|c| PyClassInfo {
+            module: "models".to_string(),
+            ..c
+        });
+        let graph = InheritanceGraph::new(&classes);
+
+        assert!(graph.parents_of(0).is_empty());
+        assert_eq!(graph.parents_of(1), &BTreeSet::from([0]));
+        assert_eq!(graph.children_of(1), &BTreeSet::from([2]));
+        assert_eq!(
+            graph.neighbourhood(&BTreeSet::from([1])),
+            BTreeSet::from([0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn test_focus() {
+        let mut classes = vec![
+            class("Base", &["pydantic.BaseModel"]),
+            class("Order", &["Base"]),
+            class("SpecialOrder", &["Order"]),
+            class("RushOrder", &["SpecialOrder"]),
+            class("Customer", &["Base"]),
+            class("Address", &["Base"]),
+        ];
+        let field = |name: &str, dtype: &str| Field {
+            name: name.to_string(),
+            dtype: Some(dtype.to_string()),
+            default: None,
+        };
+        classes[1].fields.insert(field("customer", "Customer"));
+        classes[4].fields.insert(field("address", "Address"));
+        let seeds = BTreeSet::from([1]);
+
+        let reach = Reach {
+            ancestors: None,
+            descendants: Some(1),
+            related: 0,
+        };
+        assert_eq!(focus(&classes, &seeds, reach), BTreeSet::from([0, 1, 2]));
+
+        let reach = Reach {
+            ancestors: Some(0),
+            descendants: Some(0),
+            related: 2,
+        };
+        assert_eq!(focus(&classes, &seeds, reach), BTreeSet::from([1, 4, 5]));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::json::classes_to_json;
+use crate::prelude::*;
+use crate::python::*;
+use crate::relation::*;
+
+use serde::Serialize;
+
+/// The page, with a renderer for the class model that runs offline.
+static TEMPLATE: &str = include_str!("report.html");
+
+/// Substitutes every `{{name}}` placeholder in a single pass, so that values
+/// are never searched for placeholders themselves.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let name = &after[..end];
+            let (_, value) = values.iter().find(|(n, _)| *n == name)?;
+            Some((end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                result.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// A relationship between two classes in the model, by their position in the
+/// JSON document.
+#[derive(Serialize)]
+struct Edge {
+    from: usize,
+    to: usize,
+    kind: RelationKind,
+    label: Option<String>,
+}
+
+/// Lists the relationships between classes in the model, e.g.,
+/// `{"from": 1, "to": 0, "kind": "composition", "label": "customer"}`.
+fn relations_to_json(classes: &[PyClassInfo]) -> String {
+    let edges = relations(classes)
+        .into_iter()
+        .filter_map(|r| match r.to {
+            Target::Class(to) => Some(Edge {
+                from: r.from,
+                to,
+                kind: r.kind,
+                label: r.label,
+            }),
+            Target::External(_) => None,
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string(&edges).expect("relations are always serializable")
+}
+
+/// Renders classes as a self-contained HTML page, with an interactive diagram,
+/// a searchable list of classes and their details.
+///
+/// Nothing is fetched when the page is viewed. The model is embedded as in
+/// `--format json`, along with the relationships drawn between classes, and
+/// the Mermaid diagram is included for reference.
+pub fn classes_to_html(classes: &[PyClassInfo], title: &str) -> String {
+    let mermaid = crate::classes_to_mermaid(classes, &Default::default()).unwrap_or_default();
+    let mermaid = format!("classDiagram\n\n{}\n", mermaid.trim_end());
+
+    // Classes are listed in the order of the JSON document, i.e., by file.
+    let mut classes = classes.to_vec();
+    classes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // `</script>` within a docstring would end the element early.
+    let model = classes_to_json(&classes).replace("</", "<\\/");
+    let relations = relations_to_json(&classes).replace("</", "<\\/");
+
+    fill(
+        TEMPLATE,
+        &[
+            ("title", &escape_xml(title)),
+            ("mermaid", &escape_xml(&mermaid)),
+            ("model", &model),
+            ("relations", &relations),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_html() {
+        let classes = [PyClassInfo {
+            name: "Order".to_string(),
+            docstring: Some("Ends with </script>.".to_string()),
+            ..Default::default()
+        }];
+
+        let html = classes_to_html(&classes, "shop <models>");
+        assert!(html.contains("<title>shop &lt;models&gt;</title>"));
+        assert!(html.contains("classDiagram\n\n    class Order"));
+        assert!(html.contains(r#""docstring": "Ends with <\/script>.""#));
+        assert!(!html.contains("{{"));
+        assert!(!html.contains("src=\"http"));
+    }
+
+    #[test]
+    fn test_html_relations() {
+        let classes = [
+            PyClassInfo {
+                name: "Order".to_string(),
+                path: "shop/orders.py".into(),
+                fields: BTreeSet::from([Field {
+                    name: "customer".to_string(),
+                    dtype: Some("Customer".to_string()),
+                    default: None,
+                }]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Customer".to_string(),
+                path: "shop/customers.py".into(),
+                ..Default::default()
+            },
+        ];
+
+        let html = classes_to_html(&classes, "{{model}}");
+        assert!(html.contains("<title>{{model}}</title>"));
+        assert!(html.contains(r#"[{"from":1,"to":0,"kind":"composition","label":"customer"}]"#));
+    }
+}
@@ -0,0 +1,271 @@
+use crate::prelude::*;
+use crate::python::package_of;
+
+use rustpython_parser::{ast, Parse};
+use std::collections::{BTreeMap, BTreeSet};
+
+static INDENT: &str = "    ";
+
+/// Collects import statements at the top level of a module, including those
+/// guarded by `if TYPE_CHECKING:` or in any part of a `try` statement, such
+/// as fallbacks under `except ImportError`.
+fn collect<'a>(body: &'a [ast::Stmt], imports: &mut Vec<&'a ast::Stmt>) {
+    for stmt in body {
+        match stmt {
+            ast::Stmt::Import(_) | ast::Stmt::ImportFrom(_) => imports.push(stmt),
+            ast::Stmt::If(s) => {
+                collect(&s.body, imports);
+                collect(&s.orelse, imports);
+            }
+            ast::Stmt::Try(ast::StmtTry {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            })
+            | ast::Stmt::TryStar(ast::StmtTryStar {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            }) => {
+                collect(body, imports);
+                // e.g., `import json` as a fallback for `import ujson as json`.
+                for ast::ExceptHandler::ExceptHandler(handler) in handlers {
+                    collect(&handler.body, imports);
+                }
+                collect(orelse, imports);
+                collect(finalbody, imports);
+            }
+            _ => continue,
+        }
+    }
+}
+
+/// Lists the dotted paths imported by the module `module`, with relative
+/// imports made absolute.
+///
+/// `from a import b` is listed as `a.b`, as `b` may be a module in its own
+/// right; it is up to the caller to resolve it to the longest known module.
+pub fn parse_imports(src: &str, module: &str, is_package: bool) -> Result<Vec<String>, String> {
+    let body = ast::Suite::parse(src, "path").map_err(|e| e.to_string())?;
+    let mut stmts = Vec::new();
+    collect(&body, &mut stmts);
+
+    // Relative imports start from the package a module is in, or from the
+    // package itself in the case of an `__init__.py`.
+    let mut package = module.split('.').collect::<Vec<_>>();
+    if !is_package {
+        package.pop();
+    }
+
+    let mut imports = Vec::new();
+    for stmt in stmts {
+        match stmt {
+            ast::Stmt::Import(i) => imports.extend(i.names.iter().map(|a| a.name.to_string())),
+            ast::Stmt::ImportFrom(i) => {
+                let level = i.level.as_ref().map_or(0, |l| l.to_usize());
+                let mut base = match level {
+                    0 => vec![],
+                    _ => {
+                        let keep = package.len().saturating_sub(level - 1);
+                        package[..keep].iter().map(|p| p.to_string()).collect()
+                    }
+                };
+                if let Some(m) = &i.module {
+                    base.push(m.to_string());
+                }
+                let base = base.join(".");
+                for alias in i.names.iter() {
+                    match (base.is_empty(), alias.name.as_str()) {
+                        (_, "*") => imports.push(base.clone()),
+                        (true, name) => imports.push(name.to_string()),
+                        (false, name) => imports.push(format!("{base}.{name}")),
+                    }
+                }
+            }
+            _ => continue,
+        }
+    }
+    Ok(imports)
+}
+
+/// Assigns each module a node ID, e.g., `pkg_models` for `pkg.models`, with a
+/// suffix where two modules would otherwise share one, e.g., `pkg.a_b` and
+/// `pkg_a.b`.
+fn node_ids(modules: &BTreeSet<String>) -> BTreeMap<&str, String> {
+    let mut seen = BTreeSet::new();
+    modules
+        .iter()
+        .map(|module| {
+            let base = module
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect::<String>();
+            let mut id = base.clone();
+            let mut n = 1;
+            while !seen.insert(id.clone()) {
+                n += 1;
+                id = format!("{base}_{n}");
+            }
+            (module.as_str(), id)
+        })
+        .collect()
+}
+
+/// Renders imports between modules as the body of a Mermaid flowchart, with
+/// the number of imports on each edge.
+///
+/// Only imports of the given modules are drawn. With a `depth`, modules are
+/// collapsed into their package at that depth, e.g., `pkg.models` for
+/// `pkg.models.base` at a depth of 2.
+pub fn modules_to_mermaid(
+    modules: &[(String, Vec<String>)],
+    depth: Option<usize>,
+) -> Option<String> {
+    let known = modules
+        .iter()
+        .map(|(m, _)| m.as_str())
+        .collect::<BTreeSet<_>>();
+    let resolve = |import: &str| {
+        let parts = import.split('.').collect::<Vec<_>>();
+        (1..=parts.len())
+            .rev()
+            .map(|n| parts[..n].join("."))
+            .find(|m| known.contains(m.as_str()))
+    };
+
+    let nodes = known
+        .iter()
+        .map(|m| package_of(m, depth))
+        .collect::<BTreeSet<_>>();
+    let mut edges: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for (module, imports) in modules.iter() {
+        let from = package_of(module, depth);
+        for import in imports.iter().filter_map(|i| resolve(i)) {
+            let to = package_of(&import, depth);
+            if from != to {
+                *edges.entry((from.clone(), to)).or_default() += 1;
+            }
+        }
+    }
+
+    if nodes.is_empty() {
+        return None;
+    }
+
+    let ids = node_ids(&nodes);
+    let mut result = nodes
+        .iter()
+        .map(|n| format!("{INDENT}{}[\"{n}\"]{EOL}", ids[n.as_str()]))
+        .collect::<String>();
+    if !edges.is_empty() {
+        result.push_str(EOL);
+    }
+    for ((from, to), count) in edges {
+        result.push_str(&format!(
+            "{INDENT}{} -->|{count}| {}{EOL}",
+            ids[from.as_str()],
+            ids[to.as_str()]
+        ));
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_imports() {
+        #[rustfmt::skip]
+        let py = [
+            "import os, pkg.util",
+            "from . import base",
+            "from ..core.utils import helper, other",
+            "from .impls import *",
+            "if TYPE_CHECKING:",
+            "    from pkg.core import Thing",
+            "try:",
+            "    import ujson as json",
+            "except ImportError:",
+            "    import json",
+        ].join("\n");
+
+        assert_eq!(
+            parse_imports(&py, "pkg.models.order", false).unwrap(),
+            vec![
+                "os",
+                "pkg.util",
+                "pkg.models.base",
+                "pkg.core.utils.helper",
+                "pkg.core.utils.other",
+                "pkg.models.impls",
+                "pkg.core.Thing",
+                "ujson",
+                "json",
+            ]
+        );
+        assert_eq!(
+            parse_imports("from .base import Base", "pkg.models", true).unwrap(),
+            vec!["pkg.models.base.Base"]
+        );
+    }
+
+    #[test]
+    fn test_modules_to_mermaid() {
+        let modules = [
+            (
+                "pkg.models.order".to_string(),
+                vec![
+                    "pkg.models.base.Base".to_string(),
+                    "pkg.core.utils.helper".to_string(),
+                    "pkg.core.utils.other".to_string(),
+                    "os".to_string(),
+                ],
+            ),
+            ("pkg.models.base".to_string(), vec![]),
+            ("pkg.core.utils".to_string(), vec![]),
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            "    pkg_core_utils[\"pkg.core.utils\"]",
+            "    pkg_models_base[\"pkg.models.base\"]",
+            "    pkg_models_order[\"pkg.models.order\"]",
+            "",
+            "    pkg_models_order -->|2| pkg_core_utils",
+            "    pkg_models_order -->|1| pkg_models_base",
+            "",
+        ].join(EOL);
+        assert_eq!(modules_to_mermaid(&modules, None).unwrap(), expected);
+
+        #[rustfmt::skip]
+        let expected = [
+            "    pkg_core[\"pkg.core\"]",
+            "    pkg_models[\"pkg.models\"]",
+            "",
+            "    pkg_models -->|2| pkg_core",
+            "",
+        ].join(EOL);
+        assert_eq!(modules_to_mermaid(&modules, Some(2)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_node_ids() {
+        let modules = [
+            ("pkg.a_b".to_string(), vec![]),
+            ("pkg_a.b".to_string(), vec![]),
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            "    pkg_a_b[\"pkg.a_b\"]",
+            "    pkg_a_b_2[\"pkg_a.b\"]",
+            "",
+        ].join(EOL);
+        assert_eq!(modules_to_mermaid(&modules, None).unwrap(), expected);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::python::*;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Version of the JSON document, bumped whenever a field is removed or
+/// changes meaning. Adding fields does not bump it.
+pub static SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    modules: Vec<Module<'a>>,
+}
+
+#[derive(Serialize)]
+struct Module<'a> {
+    name: &'a str,
+    path: &'a Path,
+    classes: Vec<Class<'a>>,
+}
+
+#[derive(Serialize)]
+struct Class<'a> {
+    kind: ClassKind,
+    #[serde(flatten)]
+    info: &'a PyClassInfo,
+}
+
+/// Serializes classes as a JSON document, grouped by the file defining them.
+/// See the README for the schema.
+pub fn classes_to_json(classes: &[PyClassInfo]) -> String {
+    let mut modules: BTreeMap<&Path, Module> = BTreeMap::new();
+    for cls in classes.iter() {
+        modules
+            .entry(cls.path.as_path())
+            .or_insert_with(|| Module {
+                name: &cls.module,
+                path: &cls.path,
+                classes: Vec::new(),
+            })
+            .classes
+            .push(Class {
+                kind: cls.kind(),
+                info: cls,
+            });
+    }
+
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        modules: modules.into_values().collect(),
+    };
+    serde_json::to_string_pretty(&document).expect("the model is always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_json() {
+        let classes = [PyClassInfo {
+            name: "Order".to_string(),
+            module: "shop.models".to_string(),
+            path: "src/shop/models.py".into(),
+            location: Location {
+                line: 3,
+                end_line: 8,
+            },
+            parents: BTreeSet::from(["Base".to_string()]),
+            docstring: Some("An order.".to_string()),
+            methods: BTreeSet::from([Method {
+                name: "total".to_string(),
+                args: vec![Field {
+                    name: "self".to_string(),
+                    dtype: None,
+                    default: None,
+                }],
+                returns: Some("int".to_string()),
+                decorators: vec!["property".to_string()],
+                docstring: None,
+            }]),
+            ..Default::default()
+        }];
+
+        let json: serde_json::Value = serde_json::from_str(&classes_to_json(&classes)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schema_version": 1,
+                "modules": [{
+                    "name": "shop.models",
+                    "path": "src/shop/models.py",
+                    "classes": [{
+                        "kind": "class",
+                        "name": "Order",
+                        "fields": [],
+                        "methods": [{
+                            "name": "total",
+                            "parameters": [{"name": "self", "type": null, "default": null}],
+                            "returns": "int",
+                            "decorators": ["property"],
+                            "docstring": null,
+                        }],
+                        "bases": ["Base"],
+                        "type_params": [],
+                        "decorators": [],
+                        "keywords": {},
+                        "docstring": "An order.",
+                        "module": "shop.models",
+                        "path": "src/shop/models.py",
+                        "location": {"line": 3, "end_line": 8},
+                    }],
+                }],
+            })
+        );
+
+        // The classes themselves round-trip.
+        let cls: PyClassInfo =
+            serde_json::from_value(json["modules"][0]["classes"][0].clone()).unwrap();
+        assert_eq!(cls.methods, classes[0].methods);
+    }
+}
@@ -0,0 +1,417 @@
+//! A layered (Sugiyama) layout for directed graphs, such as an inheritance
+//! hierarchy drawn with parents above their children.
+
+use std::collections::BTreeSet;
+
+/// Space between neighbouring nodes within a layer.
+static GAP_X: f64 = 40.0;
+/// Space between layers, for the edges to run through.
+static GAP_Y: f64 = 60.0;
+/// Rounds of sweeps through the layers to reduce crossings.
+static SWEEPS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn center(&self) -> Point {
+        Point {
+            x: self.x + self.width / 2.0,
+            y: self.y + self.height / 2.0,
+        }
+    }
+
+    /// The point where a line from the center towards `to` leaves the box.
+    pub fn clip(&self, to: Point) -> Point {
+        let c = self.center();
+        let (dx, dy) = (to.x - c.x, to.y - c.y);
+        if dx == 0.0 && dy == 0.0 {
+            return c;
+        }
+        let sx = (self.width / 2.0) / dx.abs();
+        let sy = (self.height / 2.0) / dy.abs();
+        let s = sx.min(sy).min(1.0);
+        Point {
+            x: c.x + dx * s,
+            y: c.y + dy * s,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Layout {
+    /// The box of each node, in the order given.
+    pub nodes: Vec<Rect>,
+    /// The route of each edge, from the top of its source to the bottom of
+    /// its target, running straight through the layers in between.
+    pub edges: Vec<Vec<Point>>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A node of the layered graph, which may be a bend in a long edge.
+struct Vertex {
+    layer: usize,
+    width: f64,
+    height: f64,
+    above: Vec<usize>,
+    below: Vec<usize>,
+}
+
+/// Assigns each node to a layer, such that every edge points at least one
+/// layer up. Edges are taken in order, and those closing a cycle are ignored.
+fn assign_layers(count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut targets = vec![Vec::new(); count];
+    let reaches = |targets: &[Vec<usize>], from: usize, to: usize| {
+        let mut stack = vec![from];
+        let mut seen = BTreeSet::new();
+        while let Some(n) = stack.pop() {
+            if n == to {
+                return true;
+            }
+            if seen.insert(n) {
+                stack.extend(targets[n].iter().copied());
+            }
+        }
+        false
+    };
+    for &(from, to) in edges.iter() {
+        if !reaches(&targets, to, from) {
+            targets[from].push(to);
+        }
+    }
+
+    fn visit(n: usize, targets: &[Vec<usize>], layers: &mut [Option<usize>]) -> usize {
+        if let Some(layer) = layers[n] {
+            return layer;
+        }
+        let layer = targets[n]
+            .iter()
+            .map(|&to| visit(to, targets, layers) + 1)
+            .max()
+            .unwrap_or(0);
+        layers[n] = Some(layer);
+        layer
+    }
+
+    let mut layers = vec![None; count];
+    (0..count)
+        .map(|n| visit(n, &targets, &mut layers))
+        .collect()
+}
+
+/// Counts the edges crossing between a layer and the one below it.
+fn crossings(upper: &[usize], vertices: &[Vertex], position: &[usize]) -> usize {
+    let edges = upper
+        .iter()
+        .flat_map(|&u| vertices[u].below.iter().map(move |&l| (u, l)))
+        .map(|(u, l)| (position[u], position[l]))
+        .collect::<Vec<_>>();
+    let mut count = 0;
+    for (i, a) in edges.iter().enumerate() {
+        for b in edges[i + 1..].iter() {
+            if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn total_crossings(layers: &[Vec<usize>], vertices: &[Vertex], position: &[usize]) -> usize {
+    layers
+        .iter()
+        .map(|l| crossings(l, vertices, position))
+        .sum()
+}
+
+/// Sorts a layer by the mean position of each vertex's neighbours, leaving
+/// vertices without any where they are.
+fn sort_by_barycenter(layer: &mut [usize], neighbours: impl Fn(usize) -> Vec<f64>) {
+    let keys = layer
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            let n = neighbours(v);
+            match n.is_empty() {
+                true => i as f64,
+                false => n.iter().sum::<f64>() / n.len() as f64,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut order = (0..layer.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| keys[a].total_cmp(&keys[b]));
+    let sorted = order.iter().map(|&i| layer[i]).collect::<Vec<_>>();
+    layer.copy_from_slice(&sorted);
+}
+
+/// Places the vertices of a layer as close to the desired centers as their
+/// order and widths allow.
+fn place(layer: &[usize], vertices: &[Vertex], desired: &[f64], x: &mut [f64]) {
+    let mut right = f64::NEG_INFINITY;
+    for (&v, &center) in layer.iter().zip(desired) {
+        let half = vertices[v].width / 2.0;
+        x[v] = center.max(right + GAP_X + half);
+        right = x[v] + half;
+    }
+    // Pushing right drifts the layer, so center it back on the desired spot.
+    let shift = layer
+        .iter()
+        .zip(desired)
+        .map(|(&v, d)| d - x[v])
+        .sum::<f64>()
+        / layer.len().max(1) as f64;
+    for &v in layer {
+        x[v] += shift;
+    }
+}
+
+/// Lays out a graph of boxes of the given sizes, with the target of each
+/// edge in a layer above its source.
+pub(crate) fn layered(sizes: &[(f64, f64)], edges: &[(usize, usize)]) -> Layout {
+    let layer_of = assign_layers(sizes.len(), edges);
+    let mut vertices = sizes
+        .iter()
+        .zip(layer_of.iter())
+        .map(|(&(width, height), &layer)| Vertex {
+            layer,
+            width,
+            height,
+            above: Vec::new(),
+            below: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    // Split edges spanning several layers with a bend in each, and turn
+    // around those that point down.
+    let mut chains = Vec::with_capacity(edges.len());
+    let mut seen = BTreeSet::new();
+    for &(from, to) in edges.iter() {
+        let (lower, upper, reversed) = match layer_of[from] > layer_of[to] {
+            true => (from, to, false),
+            false => (to, from, true),
+        };
+        let mut chain = vec![lower];
+        let mut prev = lower;
+        for layer in (vertices[upper].layer + 1..vertices[lower].layer).rev() {
+            vertices.push(Vertex {
+                layer,
+                width: 0.0,
+                height: 0.0,
+                above: Vec::new(),
+                below: Vec::new(),
+            });
+            let bend = vertices.len() - 1;
+            vertices[prev].above.push(bend);
+            vertices[bend].below.push(prev);
+            chain.push(bend);
+            prev = bend;
+        }
+        if vertices[prev].layer > vertices[upper].layer && seen.insert((prev, upper)) {
+            vertices[prev].above.push(upper);
+            vertices[upper].below.push(prev);
+        }
+        chain.push(upper);
+        if reversed {
+            chain.reverse();
+        }
+        chains.push(chain);
+    }
+
+    let depth = vertices.iter().map(|v| v.layer + 1).max().unwrap_or(0);
+    let mut layers = vec![Vec::new(); depth];
+    for (i, v) in vertices.iter().enumerate() {
+        layers[v.layer].push(i);
+    }
+
+    // Reduce crossings, sweeping down and then up, and keep the best order.
+    let mut position = vec![0; vertices.len()];
+    let index = |layers: &[Vec<usize>], position: &mut [usize]| {
+        for layer in layers.iter() {
+            for (i, &v) in layer.iter().enumerate() {
+                position[v] = i;
+            }
+        }
+    };
+    index(&layers, &mut position);
+    let mut best = (
+        total_crossings(&layers, &vertices, &position),
+        layers.clone(),
+    );
+    for sweep in 0..SWEEPS {
+        let down = sweep % 2 == 0;
+        let order = match down {
+            true => (1..depth).collect::<Vec<_>>(),
+            false => (0..depth.saturating_sub(1)).rev().collect(),
+        };
+        for l in order {
+            let mut layer = std::mem::take(&mut layers[l]);
+            sort_by_barycenter(&mut layer, |v| {
+                let n = match down {
+                    true => &vertices[v].above,
+                    false => &vertices[v].below,
+                };
+                n.iter().map(|&u| position[u] as f64).collect()
+            });
+            for (i, &v) in layer.iter().enumerate() {
+                position[v] = i;
+            }
+            layers[l] = layer;
+        }
+        let count = total_crossings(&layers, &vertices, &position);
+        if count < best.0 {
+            best = (count, layers.clone());
+        }
+    }
+    let layers = best.1;
+    index(&layers, &mut position);
+
+    // Rows from the top, each as tall as its tallest box.
+    let mut y = vec![0.0; vertices.len()];
+    let mut top = 0.0;
+    for layer in layers.iter() {
+        let height = layer
+            .iter()
+            .map(|&v| vertices[v].height)
+            .fold(0.0, f64::max);
+        for &v in layer {
+            y[v] = top + height / 2.0;
+            // Bends span the row, so that edges pass between the boxes.
+            if v >= sizes.len() {
+                vertices[v].height = height;
+            }
+        }
+        top += height + GAP_Y;
+    }
+
+    // Start packed to the left, then pull each vertex toward its neighbours.
+    let mut x = vec![0.0; vertices.len()];
+    for layer in layers.iter() {
+        let mut left = 0.0;
+        for &v in layer {
+            x[v] = left + vertices[v].width / 2.0;
+            left += vertices[v].width + GAP_X;
+        }
+    }
+    for sweep in 0..SWEEPS {
+        let down = sweep % 2 == 0;
+        let order = match down {
+            true => (0..depth).collect::<Vec<_>>(),
+            false => (0..depth).rev().collect(),
+        };
+        for l in order {
+            let desired = layers[l]
+                .iter()
+                .map(|&v| {
+                    let n = match down {
+                        true => &vertices[v].above,
+                        false => &vertices[v].below,
+                    };
+                    match n.is_empty() {
+                        true => x[v],
+                        false => n.iter().map(|&u| x[u]).sum::<f64>() / n.len() as f64,
+                    }
+                })
+                .collect::<Vec<_>>();
+            place(&layers[l], &vertices, &desired, &mut x);
+        }
+    }
+
+    let left = (0..vertices.len())
+        .map(|v| x[v] - vertices[v].width / 2.0)
+        .fold(f64::INFINITY, f64::min);
+    let left = if left.is_finite() { left } else { 0.0 };
+    let right = (0..vertices.len())
+        .map(|v| x[v] + vertices[v].width / 2.0 - left)
+        .fold(0.0, f64::max);
+
+    let rect = |v: usize| Rect {
+        x: x[v] - vertices[v].width / 2.0 - left,
+        y: y[v] - vertices[v].height / 2.0,
+        width: vertices[v].width,
+        height: vertices[v].height,
+    };
+    let edges = chains
+        .iter()
+        .map(|chain| {
+            let (first, last) = (chain[0], chain[chain.len() - 1]);
+            let down = vertices[first].layer < vertices[last].layer;
+            let mut route = Vec::with_capacity(2 * chain.len());
+            for &v in chain.iter() {
+                let r = rect(v);
+                let (top, bottom) = (
+                    Point {
+                        x: r.center().x,
+                        y: r.y,
+                    },
+                    Point {
+                        x: r.center().x,
+                        y: r.y + r.height,
+                    },
+                );
+                match (v == first, v == last, down) {
+                    (true, _, true) | (_, true, false) => route.push(bottom),
+                    (true, _, false) | (_, true, true) => route.push(top),
+                    (_, _, true) => route.extend([top, bottom]),
+                    (_, _, false) => route.extend([bottom, top]),
+                }
+            }
+            route
+        })
+        .collect();
+
+    Layout {
+        nodes: (0..sizes.len()).map(rect).collect(),
+        edges,
+        width: right,
+        height: (top - GAP_Y).max(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layered() {
+        // A diamond, with an edge skipping a layer and a cycle.
+        let sizes = [(100.0, 50.0), (80.0, 40.0), (80.0, 40.0), (60.0, 30.0)];
+        let edges = [(1, 0), (2, 0), (3, 1), (3, 2), (3, 0), (0, 3)];
+        let layout = layered(&sizes, &edges);
+
+        let [base, left, right, child] = layout.nodes[..] else {
+            panic!("a box per node")
+        };
+        assert_eq!(base.y, 0.0);
+        assert_eq!(left.y, right.y);
+        assert!(left.y > base.y + base.height);
+        assert!(child.y > left.y + left.height);
+        assert!(left.x + left.width + GAP_X <= right.x + 1e-9);
+        assert!(layout.nodes.iter().all(|n| n.x >= 0.0));
+        assert!(layout
+            .nodes
+            .iter()
+            .all(|n| n.x + n.width <= layout.width + 1e-9));
+        assert_eq!(layout.height, child.y + child.height);
+
+        // The long edge passes the layer it skips through, between the boxes.
+        assert_eq!(layout.edges[4].len(), 4);
+        assert_eq!(layout.edges[4][0].y, child.y);
+        assert_eq!(layout.edges[4][1].y, left.y + left.height);
+        assert_eq!(layout.edges[4][2].y, left.y);
+        assert_eq!(layout.edges[4][3].y, base.y + base.height);
+        // The edge closing the cycle runs the other way.
+        assert_eq!(layout.edges[5][0].y, base.y + base.height);
+    }
+}
@@ -1,18 +1,52 @@
 pub(crate) mod prelude;
 
 mod _ast;
+mod d2;
+mod diff;
+mod dot;
+mod er;
 pub mod errors;
+mod graph;
+mod html;
+mod imports;
+mod json;
+mod layout;
 mod mermaid;
+mod origin;
+mod plantuml;
 mod python;
+mod relation;
+mod style;
+mod svg;
+mod xmi;
 
-use mermaid::MermaidAdapter;
-use prelude::*;
+pub use diff::{ClassDiff, MemberDiff, MemberKind, ModelDiff, Status};
+pub use graph::{focus, InheritanceGraph, Reach};
+pub use imports::{modules_to_mermaid, parse_imports};
+pub use json::SCHEMA_VERSION;
+pub use mermaid::{Externals, LinkTemplate, MermaidOptions};
+pub use origin::{Classifier, Origin};
+pub use prelude::{Field, Location, Method};
+pub use python::{module_name, package_of, ClassKind, PyClassInfo};
+pub use relation::{relations, Relation, RelationKind, Target};
+pub use style::{Glob, StyleRule};
 
-pub fn python_to_mermaid(
-    src: String,
+/// Extracts all classes defined at the top level of a Python source.
+pub fn parse_python(src: &str) -> Result<Vec<PyClassInfo>, String> {
+    let classes = python::PyClassInfo::from_source(src)
+        .map_err(|e| e.to_string())?
+        .filter_map(|c| c.ok())
+        .collect();
+    Ok(classes)
+}
+
+/// Removes classes matching the exclude globs, along with the classes derived
+/// from base classes matching them at any depth, and the bases themselves.
+pub fn exclude_classes(
+    classes: Vec<PyClassInfo>,
     exclude_names: &[String],
     exclude_bases: &[String],
-) -> Result<Option<String>, String> {
+) -> Vec<PyClassInfo> {
     let exclude_patterns = exclude_names
         .iter()
         .map(|n| glob::Pattern::new(n).unwrap())
@@ -21,29 +55,193 @@ pub fn python_to_mermaid(
         .iter()
         .map(|n| glob::Pattern::new(n).unwrap())
         .collect::<Vec<_>>();
-    let result = python::PyClassInfo::from_source(&src)
-        .map_err(|e| e.to_string())?
-        .filter_map(|c| {
-            if let Ok(c) = c {
-                if exclude_parents
-                    .iter()
-                    .any(|p| p.matches(&c.name) || c.parents.iter().any(|pp| p.matches(pp)))
-                    || exclude_patterns.iter().any(|p| p.matches(&c.name))
-                {
-                    None
-                } else {
-                    Some(c.to_mermaid().print())
+
+    let graph = InheritanceGraph::new(&classes);
+    let is_base = |i: usize| {
+        let cls: &PyClassInfo = &classes[i];
+        exclude_parents
+            .iter()
+            .any(|p| p.matches(&cls.name) || cls.parents.iter().any(|pp| p.matches(pp)))
+    };
+    let keep = (0..classes.len())
+        .map(|i| {
+            if exclude_patterns.iter().any(|p| p.matches(&classes[i].name)) {
+                return false;
+            }
+            let mut seen = std::collections::BTreeSet::from([i]);
+            let mut stack = vec![i];
+            while let Some(j) = stack.pop() {
+                if is_base(j) {
+                    return false;
+                }
+                stack.extend(graph.parents_of(j).iter().filter(|&&k| seen.insert(k)));
+            }
+            true
+        })
+        .collect::<Vec<_>>();
+
+    classes
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(c, keep)| keep.then_some(c))
+        .collect()
+}
+
+/// Keeps only classes matching the include globs, along with the classes
+/// derived from base classes matching them at any depth, and the bases
+/// themselves. Keeps every class if there are no globs at all.
+pub fn include_classes(
+    classes: Vec<PyClassInfo>,
+    include_names: &[String],
+    include_bases: &[String],
+) -> Vec<PyClassInfo> {
+    if include_names.is_empty() && include_bases.is_empty() {
+        return classes;
+    }
+    let include_patterns = include_names
+        .iter()
+        .map(|n| glob::Pattern::new(n).unwrap())
+        .collect::<Vec<_>>();
+    let include_parents = include_bases
+        .iter()
+        .map(|n| glob::Pattern::new(n).unwrap())
+        .collect::<Vec<_>>();
+
+    let graph = InheritanceGraph::new(&classes);
+    let is_base = |i: usize| {
+        let cls: &PyClassInfo = &classes[i];
+        include_parents
+            .iter()
+            .any(|p| p.matches(&cls.name) || cls.parents.iter().any(|pp| p.matches(pp)))
+    };
+    let keep = (0..classes.len())
+        .map(|i| {
+            if include_patterns.iter().any(|p| p.matches(&classes[i].name)) {
+                return true;
+            }
+            let mut seen = std::collections::BTreeSet::from([i]);
+            let mut stack = vec![i];
+            while let Some(j) = stack.pop() {
+                if is_base(j) {
+                    return true;
                 }
-            } else {
-                None
+                stack.extend(graph.parents_of(j).iter().filter(|&&k| seen.insert(k)));
             }
+            false
+        })
+        .collect::<Vec<_>>();
+
+    classes
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(c, keep)| keep.then_some(c))
+        .collect()
+}
+
+/// Renders classes as the body of a Mermaid class diagram.
+pub fn classes_to_mermaid(classes: &[PyClassInfo], options: &MermaidOptions) -> Option<String> {
+    mermaid::classes_to_mermaid(classes, options)
+}
+
+/// Renders classes as a complete PlantUML class diagram.
+pub fn classes_to_plantuml(classes: &[PyClassInfo]) -> String {
+    plantuml::classes_to_plantuml(classes)
+}
+
+/// Renders classes as a Graphviz digraph, for `dot`, `sfdp` and the like.
+pub fn classes_to_dot(classes: &[PyClassInfo]) -> String {
+    dot::classes_to_dot(classes)
+}
+
+/// Renders classes as a D2 diagram.
+pub fn classes_to_d2(classes: &[PyClassInfo]) -> String {
+    d2::classes_to_d2(classes)
+}
+
+/// Serializes classes as a versioned JSON document.
+pub fn classes_to_json(classes: &[PyClassInfo]) -> String {
+    json::classes_to_json(classes)
+}
+
+/// Renders classes as a self-contained, interactive HTML page.
+pub fn classes_to_html(classes: &[PyClassInfo], title: &str) -> String {
+    html::classes_to_html(classes, title)
+}
+
+/// Renders classes as an SVG image, laid out without any external tools.
+pub fn classes_to_svg(classes: &[PyClassInfo]) -> String {
+    svg::classes_to_svg(classes)
+}
+
+/// Renders classes as an XMI 2.5.1 document of a UML model.
+pub fn classes_to_xmi(classes: &[PyClassInfo]) -> String {
+    xmi::classes_to_xmi(classes)
+}
+
+/// Renders ORM models as the body of a Mermaid entity relationship diagram.
+pub fn classes_to_mermaid_er(classes: &[PyClassInfo]) -> Option<String> {
+    er::classes_to_mermaid_er(classes)
+}
+
+/// Renders the classes in a Python source as the body of a Mermaid class
+/// diagram, given the module the source would be imported as.
+pub fn python_to_mermaid(
+    src: String,
+    module: &str,
+    exclude_names: &[String],
+    exclude_bases: &[String],
+    options: &MermaidOptions,
+) -> Result<Option<String>, String> {
+    let classes = parse_python(&src)?
+        .into_iter()
+        .map(|c| PyClassInfo {
+            module: module.to_string(),
+            ..c
         })
-        .collect::<Vec<_>>()
-        .join(&format!("{EOL}{EOL}"));
+        .collect();
+    let classes = exclude_classes(classes, exclude_names, exclude_bases);
+    Ok(classes_to_mermaid(&classes, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_include_classes() {
+        let class = |name: &str, parents: &[&str]| PyClassInfo {
+            name: name.to_string(),
+            module: "models".to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        let classes = vec![
+            class("Base", &["pydantic.BaseModel"]),
+            class("Order", &["models.Base"]),
+            class("RushOrder", &["Order"]),
+            class("Config", &[]),
+            class("Error", &["Exception"]),
+        ];
+        let names =
+            |classes: Vec<PyClassInfo>| classes.into_iter().map(|c| c.name).collect::<Vec<_>>();
+
+        let included = include_classes(classes.clone(), &[], &["Base".to_string()]);
+        assert_eq!(names(included), ["Base", "Order", "RushOrder"]);
+
+        let included = include_classes(
+            classes.clone(),
+            &["Config".to_string()],
+            &["*Exception".to_string()],
+        );
+        assert_eq!(names(included), ["Config", "Error"]);
+
+        assert_eq!(include_classes(classes.clone(), &[], &[]).len(), 5);
+
+        let base = ["Base".to_string()];
+        let included = include_classes(classes.clone(), &[], &base);
+        assert!(exclude_classes(included, &[], &base).is_empty());
 
-    if result.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(result))
+        let excluded = exclude_classes(classes, &["Config".to_string()], &["Order".to_string()]);
+        assert_eq!(names(excluded), ["Base", "Error"]);
     }
 }
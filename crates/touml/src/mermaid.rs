@@ -1,27 +1,314 @@
+use crate::origin::*;
 use crate::prelude::*;
 use crate::python::*;
+use crate::relation::*;
+use crate::style::*;
 
-use std::collections::BTreeSet;
+use rustpython_parser::{ast, Parse};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 
 static INDENT: &str = "    ";
 
+/// How stubs of parents outside of the project look, by where they come from.
+/// The `classDef`s are prefixed so as not to clash with those of style rules.
+static STUB_STYLES: [(&str, Origin, &str); 2] = [
+    (
+        "touml_stdlib",
+        Origin::Stdlib,
+        "fill:#f5f5f5,stroke:#999,stroke-dasharray:3 3,color:#666",
+    ),
+    (
+        "touml_thirdParty",
+        Origin::ThirdParty,
+        "fill:#fff8e6,stroke:#b38600,stroke-dasharray:3 3,color:#666",
+    ),
+];
+
 pub trait MermaidAdapter {
     fn to_mermaid(self) -> MermaidClass;
 }
 
+/// Options for rendering a Mermaid class diagram.
+#[derive(Debug, Clone, Default)]
+pub struct MermaidOptions {
+    /// Whether to group classes into a `namespace` per module.
+    pub namespaces: bool,
+    /// Groups classes by their package at this depth instead, e.g., `pkg.models`
+    /// for `pkg.models.base` at a depth of 2.
+    pub package_depth: Option<usize>,
+    /// Whether to write subscripted annotations with Mermaid's generics, e.g.,
+    /// `List~int~` for `List[int]`, and drop `typing.` and `t.` prefixes.
+    pub generics: bool,
+    /// Where to link each class to, if anywhere.
+    pub links: Option<LinkTemplate>,
+    /// How to style the classes, along with a legend.
+    pub styles: Vec<StyleRule>,
+    /// What to do with parents from the standard library or third parties.
+    pub externals: Externals,
+    /// Classes drawn in other diagrams, by qualified name, along with where
+    /// to link the stubs of those that are parents to, e.g., `pkg.models.md`.
+    pub elsewhere: BTreeMap<String, String>,
+    /// Tells where parents outside of the diagram come from, e.g., built from
+    /// the whole model before any classes were left out. Built from the
+    /// classes drawn if unset.
+    pub classifier: Option<Classifier>,
+}
+
+impl MermaidOptions {
+    /// Finds where a parent drawn in another diagram is, by its name as written
+    /// in `module`, going by the same rules as resolving it in the model.
+    fn elsewhere(&self, name: &str, module: &str) -> Option<&str> {
+        let suffix = format!(".{name}");
+        let local = (!name.contains('.')).then(|| format!("{module}.{name}"));
+        let find = |matches: &dyn Fn(&str) -> bool| {
+            self.elsewhere
+                .iter()
+                .find(|(q, _)| matches(q))
+                .map(|(_, link)| link)
+        };
+        local
+            .and_then(|l| self.elsewhere.get(&l))
+            .or_else(|| self.elsewhere.get(name))
+            .or_else(|| find(&|q| q.ends_with(&suffix)))
+            .map(String::as_str)
+    }
+}
+
+/// Ways of drawing parents from outside of the project, i.e., from the
+/// standard library or third parties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Externals {
+    /// Draws them as any other node, only declared through their edges.
+    #[default]
+    Edges,
+    /// Draws them as stubs annotated `<<external>>`, styled by where they
+    /// come from.
+    Stub,
+    /// Draws a single `external` node in their place.
+    Collapse,
+    /// Leaves them out along with their edges.
+    Drop,
+}
+
+/// A URL for each class, with `{rev}`, `{path}`, `{line}` and `{abs_path}`
+/// filled in, e.g., `https://git.example/repo/blob/{rev}/{path}#L{line}`.
+#[derive(Debug, Clone, Default)]
+pub struct LinkTemplate {
+    pub template: String,
+    /// The revision to fill in for `{rev}`.
+    pub rev: String,
+    /// The absolute directory that `{path}` is relative to, usually the root
+    /// of the repository.
+    pub root: PathBuf,
+}
+
+impl LinkTemplate {
+    /// Fills in the template for a definition at `line` in the file at `path`.
+    pub fn expand(&self, path: &Path, line: usize) -> String {
+        let abs_path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let relative = abs_path.strip_prefix(&self.root).unwrap_or(path);
+        let slashed = |p: &Path| {
+            p.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        self.template
+            .replace("{rev}", &self.rev)
+            .replace("{path}", &slashed(relative))
+            .replace("{line}", &line.to_string())
+            .replace("{abs_path}", &abs_path.to_string_lossy())
+    }
+}
+
 pub struct MermaidClass {
+    /// The node ID, unique within the diagram.
+    id: String,
     name: String,
-    parents: BTreeSet<String>,
+    /// The node IDs of the parents.
+    parents: Vec<String>,
+    /// Parents outside of the model that need a label, by node ID.
+    externals: Vec<(String, String)>,
     methods: BTreeSet<Method>,
     fields: BTreeSet<Field>,
+    /// Whether to print annotations with Mermaid's generics.
+    generics: bool,
+    /// Where the class is defined.
+    path: PathBuf,
+    line: usize,
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Refers to a namespace by name, quoting names such as `pkg.models` that
+/// Mermaid would otherwise split.
+fn reference(name: &str) -> String {
+    match is_identifier(name) {
+        true => name.to_string(),
+        false => format!("`{name}`"),
+    }
+}
+
+/// Turns a name into a node ID, e.g., `pydantic_BaseModel`.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Escapes text for use in a member or label, with entity codes for the
+/// characters that Mermaid's class diagrams give meaning to.
+///
+/// Parentheses are escaped too, as Mermaid takes any member containing them
+/// for a method.
+pub(crate) fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '#' => result.push_str("#35;"),
+            '~' => result.push_str("#126;"),
+            '<' => result.push_str("#lt;"),
+            '>' => result.push_str("#gt;"),
+            '{' => result.push_str("#123;"),
+            '}' => result.push_str("#125;"),
+            ':' => result.push_str("#58;"),
+            '"' => result.push_str("#quot;"),
+            '(' => result.push_str("#40;"),
+            ')' => result.push_str("#41;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Prints an annotation with Mermaid's generics, e.g., `List~Optional~int~~`
+/// for `typing.List[Optional[int]]`.
+fn print_generic(expr: &ast::Expr) -> Option<String> {
+    let result = match expr {
+        ast::Expr::Name(n) => escape(n.id.as_str()),
+        ast::Expr::Attribute(a) => match a.value.as_ref() {
+            ast::Expr::Name(n) if n.id.as_str() == "typing" || n.id.as_str() == "t" => {
+                escape(a.attr.as_str())
+            }
+            value => format!("{}.{}", print_generic(value)?, escape(a.attr.as_str())),
+        },
+        ast::Expr::Subscript(s) => {
+            let args = match s.slice.as_ref() {
+                ast::Expr::Tuple(t) => t
+                    .elts
+                    .iter()
+                    .map(print_generic)
+                    .collect::<Option<Vec<_>>>()?
+                    .join(", "),
+                slice => print_generic(slice)?,
+            };
+            format!("{}~{args}~", print_generic(&s.value)?)
+        }
+        // e.g., the arguments in `Callable[[int, str], None]`.
+        ast::Expr::List(l) => {
+            let elts = l
+                .elts
+                .iter()
+                .map(print_generic)
+                .collect::<Option<Vec<_>>>()?;
+            format!("[{}]", elts.join(", "))
+        }
+        ast::Expr::BinOp(b) => {
+            format!("{} | {}", print_generic(&b.left)?, print_generic(&b.right)?)
+        }
+        ast::Expr::Constant(c) => match &c.value {
+            ast::Constant::Ellipsis => "...".to_string(),
+            ast::Constant::None => "None".to_string(),
+            ast::Constant::Str(s) => escape(&format!("'{s}'")),
+            ast::Constant::Int(i) => i.to_string(),
+            ast::Constant::Bool(b) => (if *b { "True" } else { "False" }).to_string(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// Prints an annotation as a Mermaid type, parsing it back into an expression
+/// to translate it into generics if asked to.
+///
+/// Mermaid cannot parse a comma between tildes, so annotations with several
+/// arguments, e.g., `dict[str, int]`, are written as they are instead.
+fn print_type(annotation: &str, generics: bool) -> String {
+    if !generics {
+        return escape(annotation);
+    }
+    let expr = match ast::Suite::parse(annotation, "<annotation>").as_deref() {
+        Ok([ast::Stmt::Expr(e)]) => print_generic(&e.value),
+        _ => None,
+    };
+    expr.filter(|e| !e.contains(','))
+        .unwrap_or_else(|| escape(annotation))
+}
+
+/// Assigns each class a node ID: its name where that is unique, and its
+/// qualified name otherwise, e.g., `pkg_models_Base`.
+pub(crate) fn node_ids(classes: &[PyClassInfo]) -> Vec<String> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for cls in classes.iter() {
+        *counts.entry(&cls.name).or_default() += 1;
+    }
+
+    let mut seen = BTreeSet::new();
+    classes
+        .iter()
+        .map(|cls| {
+            let mut id = match counts[cls.name.as_str()] {
+                1 if is_identifier(&cls.name) => cls.name.clone(),
+                _ => sanitize(&cls.qualified_name()),
+            };
+            // The same class may be defined twice in a module.
+            let base = id.clone();
+            let mut n = 1;
+            while !seen.insert(id.clone()) {
+                n += 1;
+                id = format!("{base}_{n}");
+            }
+            id
+        })
+        .collect()
 }
 
 impl MermaidClass {
+    /// Gives the class a node ID other than its name, e.g., to tell apart
+    /// classes sharing a name.
+    pub(crate) fn with_id(self, id: String) -> Self {
+        Self { id, ..self }
+    }
+
     pub fn print(&self) -> String {
+        let mut result = self.print_class();
+        let edges = self.print_edges();
+        if !edges.is_empty() {
+            result.push_str(EOL);
+            result.push_str(&edges);
+        }
+        result
+    }
+
+    /// Defines the class as well as the fields and methods therein.
+    fn print_class(&self) -> String {
         let mut result = String::new();
 
-        // Define class as well as the fields and methods therein.
-        let class_name = format!("{INDENT}class {} {{{EOL}", self.name);
+        let class_name = match self.id == self.name {
+            true => format!("{INDENT}class {} {{{EOL}", self.id),
+            false => format!(
+                "{INDENT}class {}[\"{}\"] {{{EOL}",
+                self.id,
+                escape(&self.name)
+            ),
+        };
         result.push_str(&class_name);
 
         for field in self.make_class_fields() {
@@ -34,44 +321,46 @@ impl MermaidClass {
         }
 
         result.push_str(&format!("{INDENT}}}{EOL}"));
-
-        // Declare inhertiance relationships.
-        if !self.parents.is_empty() {
-            result.push_str(EOL);
-        }
-        for parent in self.parents.iter() {
-            if parent.contains('.') {
-                result.push_str(&format!("{INDENT}`{parent}` <|-- {}", self.name));
-            } else {
-                result.push_str(&format!("{INDENT}{parent} <|-- {}", self.name));
-            }
-            result.push_str(EOL);
-        }
-
         result
     }
 
-    fn get_access_modifier(is_public: bool) -> String {
-        match is_public {
-            true => "+".to_string(),
-            false => "-".to_string(),
-        }
+    /// Declares inheritance relationships.
+    fn print_edges(&self) -> String {
+        let declarations = self
+            .externals
+            .iter()
+            .map(|(id, label)| format!("{INDENT}class {id}[\"{}\"]{EOL}", escape(label)));
+        let edges = self
+            .parents
+            .iter()
+            .map(|parent| format!("{INDENT}{parent} <|-- {}{EOL}", self.id));
+        declarations.chain(edges).collect()
+    }
+
+    /// Links the class to wherever the template points at.
+    fn print_link(&self, links: &LinkTemplate) -> String {
+        let url = links.expand(&self.path, self.line).replace('"', "%22");
+        format!("{INDENT}click {} href \"{url}\"{EOL}", self.id)
     }
 
     fn make_class_methods(&self) -> Vec<String> {
         self.methods
             .iter()
             .map(|method| {
-                let access_modifier = Self::get_access_modifier(method.is_public());
-                let mut method_str = format!("{INDENT}{INDENT}{access_modifier} {}(", method.name);
+                let access_modifier = get_access_modifier(method.is_public());
+                let mut method_str = format!(
+                    "{INDENT}{INDENT}{access_modifier} {}(",
+                    escape(&method.name)
+                );
 
                 let args = method
                     .args
                     .iter()
                     .map(|a| {
-                        a.dtype
-                            .clone()
-                            .map_or_else(|| a.name.to_string(), |t| format!("{t} {}", a.name))
+                        a.dtype.as_deref().map_or_else(
+                            || escape(&a.name),
+                            |t| format!("{} {}", print_type(t, self.generics), escape(&a.name)),
+                        )
                     })
                     .collect::<Vec<_>>();
 
@@ -82,7 +371,7 @@ impl MermaidClass {
                 method_str.push(')');
 
                 if let Some(return_type) = &method.returns {
-                    method_str.push_str(&format!(" {return_type}"));
+                    method_str.push_str(&format!(" {}", print_type(return_type, self.generics)));
                 }
 
                 method_str
@@ -93,15 +382,19 @@ impl MermaidClass {
     fn make_class_fields(&self) -> Vec<String> {
         let mut result = Vec::with_capacity(self.fields.len());
         for field in self.fields.iter() {
-            let access_modifier = Self::get_access_modifier(field.is_public());
+            let access_modifier = get_access_modifier(field.is_public());
+            let name = escape(&field.name);
             let line = match (&field.dtype, &field.default) {
                 (Some(t), _) => {
-                    format!("{INDENT}{INDENT}{access_modifier} {} {t}", field.name)
+                    format!(
+                        "{INDENT}{INDENT}{access_modifier} {name} {}",
+                        print_type(t, self.generics)
+                    )
                 }
                 (_, Some(d)) => {
-                    format!("{INDENT}{INDENT}{access_modifier} {} = {d}", field.name)
+                    format!("{INDENT}{INDENT}{access_modifier} {name} = {}", escape(d))
                 }
-                _ => format!("{INDENT}{INDENT}{access_modifier} {}", field.name),
+                _ => format!("{INDENT}{INDENT}{access_modifier} {name}"),
             };
             result.push(line);
         }
@@ -125,13 +418,243 @@ impl MermaidAdapter for PyClassInfo {
             .filter(|f| !(f.name.starts_with("__") & f.name.ends_with("__")))
             .collect::<BTreeSet<Field>>();
 
+        let externals = self
+            .parents
+            .iter()
+            .filter(|p| !is_identifier(p))
+            .map(|p| (sanitize(p), p.clone()))
+            .collect();
         MermaidClass {
+            id: self.name.clone(),
             name: self.name,
-            parents: self.parents,
+            parents: self.parents.iter().map(|p| sanitize(p)).collect(),
+            externals,
             methods,
             fields,
+            generics: false,
+            path: self.path,
+            line: self.location.line,
+        }
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|l| match l.is_empty() {
+            true => EOL.to_string(),
+            false => format!("{INDENT}{l}{EOL}"),
+        })
+        .collect()
+}
+
+/// Renders classes as the body of a Mermaid class diagram.
+///
+/// Parents are resolved to classes in the model where possible, so that
+/// classes sharing a name in different modules remain distinct nodes.
+pub fn classes_to_mermaid(classes: &[PyClassInfo], options: &MermaidOptions) -> Option<String> {
+    if classes.is_empty() {
+        return None;
+    }
+
+    let ids = node_ids(classes);
+    let mut parents = vec![Vec::new(); classes.len()];
+    // Parents outside of the model are declared once, up front, with a label
+    // where their name is not a valid ID.
+    let mut externals: BTreeMap<String, String> = BTreeMap::new();
+    let mut unresolved = BTreeMap::new();
+    let local;
+    let classifier = match &options.classifier {
+        Some(classifier) => classifier,
+        None => {
+            local = Classifier::new(classes);
+            &local
+        }
+    };
+    // Stubs by node ID, along with the names they stand for.
+    let mut stubs: BTreeMap<String, (String, Origin)> = BTreeMap::new();
+    // Stubs of parents drawn in other diagrams, by node ID, with their links.
+    let mut elsewhere: BTreeMap<String, (String, String)> = BTreeMap::new();
+    let collapsed = match ids.iter().any(|id| id == "external") {
+        true => "external_".to_string(),
+        false => "external".to_string(),
+    };
+    let mut is_collapsed = false;
+    for relation in relations(classes) {
+        if !matches!(
+            relation.kind,
+            RelationKind::Inheritance | RelationKind::Realization
+        ) {
+            continue;
+        }
+        let link = match &relation.to {
+            Target::Class(_) => None,
+            Target::External(name) => options.elsewhere(name, &classes[relation.from].module),
+        };
+        let origin = match &relation.to {
+            Target::Class(_) => Origin::Project,
+            Target::External(_) if link.is_some() => Origin::Project,
+            Target::External(name) => classifier.classify(name),
+        };
+        if origin != Origin::Project {
+            match options.externals {
+                Externals::Drop => continue,
+                Externals::Collapse => {
+                    if !parents[relation.from].contains(&collapsed) {
+                        parents[relation.from].push(collapsed.clone());
+                    }
+                    is_collapsed = true;
+                    continue;
+                }
+                Externals::Edges | Externals::Stub => (),
+            }
+        }
+        let is_stub = options.externals == Externals::Stub && origin != Origin::Project;
+        let id = match relation.to {
+            Target::Class(j) => ids[j].clone(),
+            Target::External(name) if is_identifier(&name) => {
+                if let Some(link) = link {
+                    elsewhere.insert(name.clone(), (name.clone(), link.to_string()));
+                } else {
+                    unresolved.insert(name.clone(), name.clone());
+                }
+                if is_stub {
+                    stubs.insert(name.clone(), (name.clone(), origin));
+                }
+                name
+            }
+            Target::External(name) => {
+                let id = externals.entry(name.clone()).or_insert_with(|| {
+                    let id = sanitize(&name);
+                    match ids.contains(&id) {
+                        true => format!("{id}_external"),
+                        false => id,
+                    }
+                });
+                if let Some(link) = link {
+                    elsewhere.insert(id.clone(), (name.clone(), link.to_string()));
+                } else {
+                    unresolved.insert(name.clone(), id.clone());
+                }
+                if is_stub {
+                    stubs.insert(id.clone(), (name, origin));
+                }
+                id.clone()
+            }
+        };
+        parents[relation.from].push(id);
+    }
+    if is_collapsed {
+        unresolved.insert("external".to_string(), collapsed.clone());
+    }
+    let styles = print_styles(
+        &options.styles,
+        classes,
+        &ids,
+        &unresolved.into_iter().collect::<Vec<_>>(),
+    );
+    let nodes = classes
+        .iter()
+        .zip(ids)
+        .zip(parents)
+        .map(|((cls, id), parents)| MermaidClass {
+            id,
+            parents,
+            externals: Vec::new(),
+            generics: options.generics,
+            ..cls.clone().to_mermaid()
+        })
+        .collect::<Vec<_>>();
+
+    let mut blocks = Vec::new();
+    let mut declarations = externals
+        .iter()
+        .map(|(name, id)| format!("{INDENT}class {id}[\"{}\"]{EOL}", escape(name)))
+        .collect::<String>();
+    for (id, _) in stubs.iter().filter(|(_, (name, _))| is_identifier(name)) {
+        declarations.push_str(&format!("{INDENT}class {id}{EOL}"));
+    }
+    for id in stubs.keys() {
+        declarations.push_str(&format!("{INDENT}<<external>> {id}{EOL}"));
+    }
+    for (class, origin, style) in STUB_STYLES {
+        let ids = stubs
+            .iter()
+            .filter(|(_, (_, o))| *o == origin)
+            .map(|(id, _)| id.as_str())
+            .collect::<Vec<_>>();
+        if !ids.is_empty() {
+            declarations.push_str(&format!("{INDENT}classDef {class} {style}{EOL}"));
+            declarations.push_str(&format!(
+                "{INDENT}cssClass \"{}\" {class}{EOL}",
+                ids.join(",")
+            ));
+        }
+    }
+    for (id, (name, link)) in elsewhere.iter() {
+        if is_identifier(name) {
+            declarations.push_str(&format!("{INDENT}class {id}{EOL}"));
+        }
+        let link = link.replace('"', "%22");
+        declarations.push_str(&format!("{INDENT}click {id} href \"{link}\"{EOL}"));
+    }
+    if is_collapsed {
+        let (_, _, style) = STUB_STYLES[0];
+        let class = "touml_external";
+        declarations.push_str(&format!("{INDENT}class {collapsed}{EOL}"));
+        declarations.push_str(&format!("{INDENT}<<external>> {collapsed}{EOL}"));
+        declarations.push_str(&format!("{INDENT}classDef {class} {style}{EOL}"));
+        declarations.push_str(&format!("{INDENT}cssClass \"{collapsed}\" {class}{EOL}"));
+    }
+    if !declarations.is_empty() {
+        blocks.push(declarations);
+    }
+
+    let links = |n: &MermaidClass| {
+        options
+            .links
+            .as_ref()
+            .map(|l| n.print_link(l))
+            .unwrap_or_default()
+    };
+
+    if !options.namespaces {
+        blocks.extend(nodes.iter().map(|n| n.print() + &links(n)));
+        blocks.extend(styles);
+        return Some(blocks.join(&format!("{EOL}{EOL}")));
+    }
+
+    // Namespaces may only hold classes, so edges follow all of them.
+    let mut namespaces: BTreeMap<String, Vec<&MermaidClass>> = BTreeMap::new();
+    for (cls, node) in classes.iter().zip(nodes.iter()) {
+        namespaces
+            .entry(package_of(&cls.module, options.package_depth))
+            .or_default()
+            .push(node);
+    }
+    for (namespace, nodes) in namespaces.iter() {
+        let classes = nodes
+            .iter()
+            .map(|n| n.print_class())
+            .collect::<Vec<_>>()
+            .join(EOL);
+        match namespace.is_empty() {
+            true => blocks.push(classes),
+            false => blocks.push(format!(
+                "{INDENT}namespace {} {{{EOL}{}{INDENT}}}{EOL}",
+                reference(namespace),
+                indent(&classes)
+            )),
         }
     }
+    let edges = nodes
+        .iter()
+        .map(|n| n.print_edges() + &links(n))
+        .collect::<String>();
+    if !edges.is_empty() {
+        blocks.push(edges);
+    }
+    blocks.extend(styles);
+    Some(blocks.join(&format!("{EOL}{EOL}")))
 }
 
 //pub fn make_class_diagram<T>(nodes: impl Iterator<Item = T>) -> String
@@ -163,6 +686,7 @@ mod tests {
                 default: None,
             }]),
             methods: BTreeSet::new(),
+            ..Default::default()
         };
         assert_eq!(
             format!("{}", cls.to_mermaid().print()),
@@ -178,4 +702,282 @@ mod tests {
             .join(EOL)
         )
     }
+
+    #[test]
+    fn test_mermaid_namespaces() {
+        let class = |name: &str, module: &str, parent: &str| PyClassInfo {
+            name: name.to_string(),
+            module: module.to_string(),
+            parents: BTreeSet::from([parent.to_string()]),
+            ..Default::default()
+        };
+        let classes = [
+            class("Base", "pkg.models.base", "pydantic.BaseModel"),
+            class("Order", "pkg.models.order", "Base"),
+            class("Base", "pkg.views", "View"),
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            "    class pydantic_BaseModel[\"pydantic.BaseModel\"]",
+            "",
+            "",
+            "    class pkg_models_base_Base[\"Base\"] {",
+            "    }",
+            "",
+            "    pydantic_BaseModel <|-- pkg_models_base_Base",
+            "",
+            "",
+            "    class Order {",
+            "    }",
+            "",
+            "    pkg_models_base_Base <|-- Order",
+            "",
+            "",
+            "    class pkg_views_Base[\"Base\"] {",
+            "    }",
+            "",
+            "    View <|-- pkg_views_Base",
+            "",
+        ].join(EOL);
+        let options = MermaidOptions::default();
+        assert_eq!(classes_to_mermaid(&classes, &options).unwrap(), expected);
+
+        #[rustfmt::skip]
+        let expected = [
+            "    class pydantic_BaseModel[\"pydantic.BaseModel\"]",
+            "",
+            "",
+            "    namespace `pkg.models` {",
+            "        class pkg_models_base_Base[\"Base\"] {",
+            "        }",
+            "",
+            "        class Order {",
+            "        }",
+            "    }",
+            "",
+            "",
+            "    namespace `pkg.views` {",
+            "        class pkg_views_Base[\"Base\"] {",
+            "        }",
+            "    }",
+            "",
+            "",
+            "    pydantic_BaseModel <|-- pkg_models_base_Base",
+            "    pkg_models_base_Base <|-- Order",
+            "    View <|-- pkg_views_Base",
+            "",
+        ].join(EOL);
+        let options = MermaidOptions {
+            namespaces: true,
+            package_depth: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(classes_to_mermaid(&classes, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_mermaid_escaping() {
+        #[rustfmt::skip]
+        let corpus = [
+            ("dict[str, int] | None", "dict[str, int] | None"),
+            ("Callable[[int], str]", "Callable[[int], str]"),
+            ("Literal['a:b', \"c\"]", "Literal['a#58;b', #quot;c#quot;]"),
+            ("Literal['<b>', '#fff']", "Literal['#lt;b#gt;', '#35;fff']"),
+            ("Annotated[int, Field(gt=0)]", "Annotated[int, Field#40;gt=0#41;]"),
+            ("dict[str, Any] = {}", "dict[str, Any] = #123;#125;"),
+            ("~T", "#126;T"),
+        ];
+        for (text, expected) in corpus {
+            assert_eq!(escape(text), expected);
+        }
+
+        let cls = PyClassInfo {
+            name: "Config".to_string(),
+            parents: BTreeSet::from(["pydantic.BaseModel".to_string()]),
+            fields: BTreeSet::from([
+                Field {
+                    name: "colors".to_string(),
+                    dtype: Some("dict[str, Literal['#fff']]".to_string()),
+                    default: None,
+                },
+                Field {
+                    name: "extra".to_string(),
+                    dtype: None,
+                    default: Some("Field(default_factory=dict)".to_string()),
+                },
+            ]),
+            methods: BTreeSet::from([Method {
+                name: "get".to_string(),
+                args: vec![Field {
+                    name: "key".to_string(),
+                    dtype: Some("Literal['a:b']".to_string()),
+                    default: None,
+                }],
+                returns: Some("Callable[..., Awaitable[None]] | None".to_string()),
+                decorators: vec![],
+                docstring: None,
+            }]),
+            ..Default::default()
+        };
+
+        #[rustfmt::skip]
+        let expected = [
+            "    class pydantic_BaseModel[\"pydantic.BaseModel\"]",
+            "",
+            "",
+            "    class Config {",
+            "        + colors dict[str, Literal['#35;fff']]",
+            "        + extra = Field#40;default_factory=dict#41;",
+            "        + get(Literal['a#58;b'] key) Callable[..., Awaitable[None]] | None",
+            "    }",
+            "",
+            "    pydantic_BaseModel <|-- Config",
+            "",
+        ].join(EOL);
+        assert_eq!(
+            classes_to_mermaid(&[cls], &MermaidOptions::default()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_mermaid_generics() {
+        #[rustfmt::skip]
+        let corpus = [
+            ("int", "int"),
+            ("list[int]", "list~int~"),
+            ("typing.List[t.Optional[int]]", "List~Optional~int~~"),
+            ("typing.Dict[str, t.List[int]]", "typing.Dict[str, t.List[int]]"),
+            ("dict[str, int]", "dict[str, int]"),
+            ("Optional[dict[str, list[int]]]", "Optional[dict[str, list[int]]]"),
+            ("Callable[[int, str], None]", "Callable[[int, str], None]"),
+            ("Literal['a,b']", "Literal['a,b']"),
+            ("models.Order | None", "models.Order | None"),
+            ("Literal['a:b']", "Literal~'a#58;b'~"),
+        ];
+        for (annotation, expected) in corpus {
+            assert_eq!(print_type(annotation, true), expected);
+        }
+    }
+
+    #[test]
+    fn test_mermaid_links() {
+        let root = std::path::absolute("repo").unwrap();
+        let cls = PyClassInfo {
+            name: "Order".to_string(),
+            path: root.join("pkg").join("models.py"),
+            location: Location {
+                line: 12,
+                end_line: 20,
+            },
+            ..Default::default()
+        };
+        let options = MermaidOptions {
+            links: Some(LinkTemplate {
+                template: "https://git.example/repo/blob/{rev}/{path}#L{line}".to_string(),
+                rev: "abc123".to_string(),
+                root,
+            }),
+            ..Default::default()
+        };
+
+        #[rustfmt::skip]
+        let expected = [
+            "    class Order {",
+            "    }",
+            "    click Order href \"https://git.example/repo/blob/abc123/pkg/models.py#L12\"",
+            "",
+        ].join(EOL);
+        assert_eq!(classes_to_mermaid(&[cls], &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_mermaid_externals() {
+        let class = |name: &str, parents: &[&str]| PyClassInfo {
+            name: name.to_string(),
+            module: "shop.models".to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        let classes = [
+            class("Base", &["pydantic.BaseModel", "ABC"]),
+            class("Order", &["Base", "shop.db.Row"]),
+        ];
+        let render = |externals| {
+            let options = MermaidOptions {
+                externals,
+                ..Default::default()
+            };
+            classes_to_mermaid(&classes, &options).unwrap()
+        };
+
+        #[rustfmt::skip]
+        let expected = [
+            "    class pydantic_BaseModel[\"pydantic.BaseModel\"]",
+            "    class shop_db_Row[\"shop.db.Row\"]",
+            "    class ABC",
+            "    <<external>> ABC",
+            "    <<external>> pydantic_BaseModel",
+            "    classDef touml_stdlib fill:#f5f5f5,stroke:#999,stroke-dasharray:3 3,color:#666",
+            "    cssClass \"ABC\" touml_stdlib",
+            "    classDef touml_thirdParty fill:#fff8e6,stroke:#b38600,stroke-dasharray:3 3,color:#666",
+            "    cssClass \"pydantic_BaseModel\" touml_thirdParty",
+            "",
+        ].join(EOL);
+        assert!(render(Externals::Stub).starts_with(&expected));
+
+        let collapsed = render(Externals::Collapse);
+        assert!(collapsed.contains("    class external\n    <<external>> external"));
+        assert_eq!(collapsed.matches("external <|-- Base").count(), 1);
+        assert!(collapsed.contains("shop_db_Row <|-- Order"));
+        assert!(collapsed.contains("cssClass \"external\" touml_external"));
+
+        let dropped = render(Externals::Drop);
+        assert!(!dropped.contains("ABC") && !dropped.contains("pydantic"));
+        assert!(dropped.contains("shop_db_Row <|-- Order"));
+
+        // Parents left out of the diagram are still the project's.
+        let mixin = class("Mixin", &[]);
+        let options = MermaidOptions {
+            externals: Externals::Drop,
+            classifier: Some(Classifier::new(&[classes[1].clone(), mixin])),
+            ..Default::default()
+        };
+        let order = class("Order", &["Mixin"]);
+        assert!(classes_to_mermaid(&[order], &options)
+            .unwrap()
+            .contains("Mixin <|-- Order"));
+    }
+
+    #[test]
+    fn test_mermaid_elsewhere() {
+        let classes = [PyClassInfo {
+            name: "Order".to_string(),
+            module: "shop.orders".to_string(),
+            parents: BTreeSet::from(["Base".to_string(), "shop.db.Row".to_string()]),
+            ..Default::default()
+        }];
+        let options = MermaidOptions {
+            externals: Externals::Drop,
+            elsewhere: BTreeMap::from([
+                ("shop.models.Base".to_string(), "shop.models.md".to_string()),
+                ("shop.db.Row".to_string(), "shop.db.md".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        #[rustfmt::skip]
+        let expected = [
+            "    class shop_db_Row[\"shop.db.Row\"]",
+            "    class Base",
+            "    click Base href \"shop.models.md\"",
+            "    click shop_db_Row href \"shop.db.md\"",
+            "",
+        ].join(EOL);
+        let diagram = classes_to_mermaid(&classes, &options).unwrap();
+        assert!(diagram.starts_with(&expected), "{diagram}");
+        assert!(diagram.contains("Base <|-- Order"));
+        assert!(!diagram.contains("<<external>>"));
+    }
 }
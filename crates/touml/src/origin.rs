@@ -0,0 +1,107 @@
+use crate::python::PyClassInfo;
+
+use std::collections::BTreeSet;
+
+/// Top-level modules of the standard library that classes are commonly
+/// derived from.
+#[rustfmt::skip]
+static STDLIB_MODULES: [&str; 66] = [
+    "abc", "argparse", "array", "ast", "asyncio", "builtins", "codecs", "collections",
+    "concurrent", "configparser", "contextlib", "copy", "csv", "ctypes", "dataclasses",
+    "datetime", "decimal", "email", "enum", "fractions", "functools", "graphlib", "hashlib",
+    "html", "http", "importlib", "inspect", "io", "ipaddress", "itertools", "json",
+    "logging", "multiprocessing", "numbers", "operator", "os", "pathlib", "pickle", "queue",
+    "random", "re", "reprlib", "sched", "selectors", "shelve", "socket", "socketserver",
+    "sqlite3", "ssl", "string", "struct", "subprocess", "tempfile", "threading", "time",
+    "tkinter", "traceback", "types", "typing", "unittest", "urllib", "uuid", "warnings",
+    "weakref", "xml", "zoneinfo",
+];
+
+/// Builtins, and names commonly imported from the standard library, that
+/// classes are derived from without a module, e.g., `ABC` or `Enum`.
+#[rustfmt::skip]
+static STDLIB_NAMES: [&str; 70] = [
+    "object", "type", "int", "float", "complex", "str", "bytes", "bytearray", "dict",
+    "list", "tuple", "set", "frozenset", "property", "BaseException", "Exception",
+    "ArithmeticError", "AssertionError", "AttributeError", "ConnectionError",
+    "FileNotFoundError", "ImportError", "IndexError", "KeyError", "LookupError",
+    "NameError", "NotImplementedError", "OSError", "PermissionError", "RuntimeError",
+    "StopIteration", "TimeoutError", "TypeError", "UnicodeError", "ValueError",
+    "ZeroDivisionError", "Warning", "UserWarning", "DeprecationWarning", "ABC", "ABCMeta",
+    "Enum", "IntEnum", "StrEnum", "Flag", "IntFlag", "Protocol", "Generic", "NamedTuple",
+    "TypedDict", "Callable", "Iterable", "Iterator", "Mapping", "MutableMapping",
+    "Sequence", "MutableSequence", "OrderedDict", "defaultdict", "Counter", "UserDict",
+    "UserList", "UserString", "Thread", "Path", "Decimal", "JSONEncoder", "Handler",
+    "Formatter", "TestCase",
+];
+
+/// Where a class comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    Project,
+    Stdlib,
+    ThirdParty,
+}
+
+/// Tells where parents that could not be resolved to a class in the model
+/// come from, going by their names alone.
+///
+/// Dotted names are classified by their top-level module, while bare names
+/// are taken to be the project's if a class of the model has that name, and
+/// else third-party unless they are common in the standard library, as the
+/// imports behind them are not known.
+#[derive(Debug, Clone, Default)]
+pub struct Classifier {
+    /// Top-level packages of the model, e.g., `pkg` for `pkg.models`.
+    roots: BTreeSet<String>,
+    /// Names of the classes in the model, e.g., `Base`.
+    names: BTreeSet<String>,
+}
+
+impl Classifier {
+    pub fn new(classes: &[PyClassInfo]) -> Self {
+        let roots = classes
+            .iter()
+            .filter_map(|c| c.module.split('.').next())
+            .filter(|r| !r.is_empty())
+            .map(String::from)
+            .collect();
+        let names = classes.iter().map(|c| c.name.clone()).collect();
+        Self { roots, names }
+    }
+
+    pub fn classify(&self, name: &str) -> Origin {
+        match name.split_once('.') {
+            Some((root, _)) if self.roots.contains(root) => Origin::Project,
+            Some((root, _)) if STDLIB_MODULES.contains(&root) => Origin::Stdlib,
+            None if self.names.contains(name) => Origin::Project,
+            None if STDLIB_NAMES.contains(&name) => Origin::Stdlib,
+            _ => Origin::ThirdParty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let classes = [PyClassInfo {
+            name: "Order".to_string(),
+            module: "shop.models".to_string(),
+            ..Default::default()
+        }];
+        let classifier = Classifier::new(&classes);
+
+        assert_eq!(classifier.classify("shop.base.Base"), Origin::Project);
+        assert_eq!(classifier.classify("Order"), Origin::Project);
+        assert_eq!(classifier.classify("abc.ABC"), Origin::Stdlib);
+        assert_eq!(classifier.classify("Exception"), Origin::Stdlib);
+        assert_eq!(
+            classifier.classify("pydantic.BaseModel"),
+            Origin::ThirdParty
+        );
+        assert_eq!(classifier.classify("BaseModel"), Origin::ThirdParty);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::prelude::*;
+use crate::python::*;
+use crate::relation::*;
+
+use std::collections::BTreeMap;
+
+static INDENT: &str = "    ";
+
+fn class_keyword(kind: ClassKind) -> &'static str {
+    match kind {
+        ClassKind::Class => "class",
+        ClassKind::Abstract => "abstract class",
+        ClassKind::Interface => "interface",
+        ClassKind::Enum => "enum",
+    }
+}
+
+fn arrow(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Inheritance => "<|--",
+        RelationKind::Realization => "<|..",
+        RelationKind::Composition => "*--",
+        RelationKind::Aggregation => "o--",
+        RelationKind::Association => "-->",
+        RelationKind::Dependency => "..>",
+    }
+}
+
+fn make_field(field: &Field, kind: ClassKind) -> String {
+    // Enum members are listed by name alone.
+    if kind == ClassKind::Enum {
+        return field.name.clone();
+    }
+
+    let mut line = String::new();
+    if field.is_static() {
+        line.push_str("{static} ");
+    }
+    // Defaults such as `field(default_factory=list)` would otherwise be
+    // mistaken for a method.
+    if field.default.as_ref().is_some_and(|d| d.contains('(')) {
+        line.push_str("{field} ");
+    }
+    line.push_str(get_access_modifier(field.is_public()));
+    line.push_str(&field.name);
+    if let Some(dtype) = &field.dtype {
+        line.push_str(&format!(" : {dtype}"));
+    }
+    if let Some(default) = &field.default {
+        line.push_str(&format!(" = {default}"));
+    }
+    line
+}
+
+fn make_method(method: &Method) -> String {
+    let mut line = String::new();
+    if method.is_static() {
+        line.push_str("{static} ");
+    }
+    if method.is_abstract() {
+        line.push_str("{abstract} ");
+    }
+    let args = method
+        .args
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    line.push_str(get_access_modifier(method.is_public()));
+    line.push_str(&format!("{}({args})", method.name));
+    if let Some(returns) = &method.returns {
+        line.push_str(&format!(" : {returns}"));
+    }
+    line
+}
+
+fn make_class(cls: &PyClassInfo, indent: &str) -> String {
+    let kind = cls.kind();
+    let mut name = cls.name.clone();
+    if !cls.type_params.is_empty() {
+        name.push_str(&format!("<{}>", cls.type_params.join(", ")));
+    }
+
+    let mut result = format!("{indent}{} {name} {{{EOL}", class_keyword(kind));
+    for field in cls.fields.iter().filter(|f| !f.is_dunder()) {
+        result.push_str(&format!("{indent}{INDENT}{}{EOL}", make_field(field, kind)));
+    }
+    for method in cls.methods.iter().filter(|m| !m.is_dunder()) {
+        result.push_str(&format!("{indent}{INDENT}{}{EOL}", make_method(method)));
+    }
+    result.push_str(&format!("{indent}}}{EOL}"));
+    result
+}
+
+/// Renders classes as a complete PlantUML class diagram, with a package for
+/// each module and every relationship between the classes.
+pub fn classes_to_plantuml(classes: &[PyClassInfo]) -> String {
+    let mut result = format!("@startuml{EOL}");
+
+    let mut modules: BTreeMap<&str, Vec<&PyClassInfo>> = BTreeMap::new();
+    for cls in classes.iter() {
+        modules.entry(cls.module.as_str()).or_default().push(cls);
+    }
+    for (module, classes) in modules {
+        result.push_str(EOL);
+        if module.is_empty() {
+            for cls in classes {
+                result.push_str(&make_class(cls, ""));
+            }
+            continue;
+        }
+
+        result.push_str(&format!("package {module} {{{EOL}"));
+        for cls in classes {
+            result.push_str(&make_class(cls, INDENT));
+        }
+        result.push_str(&format!("}}{EOL}"));
+    }
+
+    let relations = relations(classes);
+    if !relations.is_empty() {
+        result.push_str(EOL);
+    }
+    for relation in relations {
+        let from = classes[relation.from].qualified_name();
+        let to = match relation.to {
+            Target::Class(j) => classes[j].qualified_name(),
+            Target::External(name) => name,
+        };
+        let line = match relation.kind {
+            // Arrows point from the parent, as in `Base <|-- Order`.
+            RelationKind::Inheritance | RelationKind::Realization => {
+                format!("{to} {} {from}", arrow(relation.kind))
+            }
+            _ => format!("{from} {} {to}", arrow(relation.kind)),
+        };
+        result.push_str(&line);
+        if let Some(label) = relation.label {
+            result.push_str(&format!(" : {label}"));
+        }
+        result.push_str(EOL);
+    }
+
+    result.push_str(EOL);
+    result.push_str(&format!("@enduml{EOL}"));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_plantuml() {
+        let classes = [
+            PyClassInfo {
+                name: "Repository".to_string(),
+                module: "store.base".to_string(),
+                parents: BTreeSet::from(["abc.ABC".to_string()]),
+                type_params: vec!["T".to_string()],
+                fields: BTreeSet::from([Field {
+                    name: "count".to_string(),
+                    dtype: Some("ClassVar[int]".to_string()),
+                    default: Some("0".to_string()),
+                }]),
+                methods: BTreeSet::from([Method {
+                    name: "get".to_string(),
+                    args: vec![Field {
+                        name: "self".to_string(),
+                        dtype: None,
+                        default: None,
+                    }],
+                    returns: Some("T".to_string()),
+                    decorators: vec!["abstractmethod".to_string()],
+                    docstring: None,
+                }]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "OrderRepository".to_string(),
+                module: "store.orders".to_string(),
+                parents: BTreeSet::from(["Repository".to_string()]),
+                fields: BTreeSet::from([Field {
+                    name: "_cache".to_string(),
+                    dtype: Some("dict[str, Repository]".to_string()),
+                    default: None,
+                }]),
+                ..Default::default()
+            },
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            "@startuml",
+            "",
+            "package store.base {",
+            "    abstract class Repository<T> {",
+            "        {static} +count : ClassVar[int] = 0",
+            "        {abstract} +get(self) : T",
+            "    }",
+            "}",
+            "",
+            "package store.orders {",
+            "    class OrderRepository {",
+            "        -_cache : dict[str, Repository]",
+            "    }",
+            "}",
+            "",
+            "abc.ABC <|-- store.base.Repository",
+            "store.base.Repository <|-- store.orders.OrderRepository",
+            "store.orders.OrderRepository o-- store.base.Repository : _cache",
+            "",
+            "@enduml",
+            "",
+        ].join(EOL);
+        assert_eq!(classes_to_plantuml(&classes), expected);
+    }
+}
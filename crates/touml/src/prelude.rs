@@ -1,35 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
 #[cfg(windows)]
 pub static EOL: &str = "\r\n";
 
 #[cfg(not(windows))]
 pub static EOL: &str = "\n";
 
+/// The UML marker of a member's visibility, e.g., `+` for public.
+pub(crate) fn get_access_modifier(is_public: bool) -> &'static str {
+    match is_public {
+        true => "+",
+        false => "-",
+    }
+}
+
+/// Escapes text for XML, HTML and SVG, both as content and within quoted
+/// attributes.
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
 pub trait Accessible {
     fn is_public(&self) -> bool;
+    fn is_dunder(&self) -> bool;
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Field {
     pub name: String,
+    #[serde(rename = "type")]
     pub dtype: Option<String>,
     pub default: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Method {
     pub name: String,
+    #[serde(rename = "parameters")]
     pub args: Vec<Field>,
     pub returns: Option<String>,
+    pub decorators: Vec<String>,
+    pub docstring: Option<String>,
+}
+
+/// A call assigned to a class attribute, with its arguments printed, e.g.,
+/// `mapped_column(ForeignKey('users.id'), primary_key=True)`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Call {
+    pub func: String,
+    pub args: Vec<String>,
+    pub keywords: BTreeMap<String, String>,
+}
+
+impl Call {
+    /// The name of the function called, less any module, e.g., `ForeignKey`
+    /// for `models.ForeignKey`.
+    pub fn name(&self) -> &str {
+        last_segment(&self.func)
+    }
+}
+
+fn last_segment(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+impl Field {
+    /// Whether the field belongs to the class rather than its instances, i.e.,
+    /// is annotated as `ClassVar`.
+    pub fn is_static(&self) -> bool {
+        self.dtype
+            .as_deref()
+            .and_then(|t| t.split('[').next())
+            .is_some_and(|t| last_segment(t) == "ClassVar")
+    }
+}
+
+impl Method {
+    fn has_decorator(&self, name: &str) -> bool {
+        self.decorators.iter().any(|d| last_segment(d) == name)
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.has_decorator("staticmethod") || self.has_decorator("classmethod")
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.has_decorator("abstractmethod")
+    }
+}
+
+/// Where a definition sits in its source file, as 1-indexed, inclusive lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub line: usize,
+    pub end_line: usize,
+}
+
+impl Location {
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.line <= end && start <= self.end_line
+    }
 }
 
 impl Accessible for Field {
     fn is_public(&self) -> bool {
         !self.name.starts_with('_')
     }
+
+    fn is_dunder(&self) -> bool {
+        self.name.starts_with("__") && self.name.ends_with("__")
+    }
 }
 
 impl Accessible for Method {
     fn is_public(&self) -> bool {
         !self.name.starts_with('_')
     }
+
+    fn is_dunder(&self) -> bool {
+        self.name.starts_with("__") && self.name.ends_with("__")
+    }
+}
+
+/// Prints fields as they would be declared in Python, e.g., `name: str = 'x'`.
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(dtype) = &self.dtype {
+            write!(f, ": {dtype}")?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " = {default}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints methods as their Python signature, e.g., `get(self, key: str) -> int`.
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{}({args})", self.name)?;
+        if let Some(returns) = &self.returns {
+            write!(f, " -> {returns}")?;
+        }
+        Ok(())
+    }
 }
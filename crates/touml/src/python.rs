@@ -1,13 +1,18 @@
-use crate::_ast::PyExpr;
+use crate::_ast::{get_docstring, PyExpr};
 use crate::errors;
 use crate::prelude::*;
 
 use rustpython_parser::{ast, Parse};
-use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
 
 pub type ParseResult<T> = core::result::Result<T, errors::ParseError>;
 type Result<T> = ParseResult<T>;
 
+static ENUM_BASES: [&str; 5] = ["Enum", "IntEnum", "StrEnum", "Flag", "IntFlag"];
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct PyClassInfo {
     pub name: String,
     pub fields: BTreeSet<Field>,
@@ -15,39 +20,251 @@ pub struct PyClassInfo {
 
     /// NOTE: With BTreeSet and the ordering inherent therein, we cannot
     /// preserve MRO when parsing parents of a Python class.
+    #[serde(rename = "bases")]
     pub parents: BTreeSet<String>,
+
+    /// Type variables the class is generic over, e.g., `T` for `Generic[T]`.
+    pub type_params: Vec<String>,
+    pub decorators: Vec<String>,
+
+    /// Keyword arguments of the class definition, e.g., `metaclass=ABCMeta`.
+    pub keywords: BTreeMap<String, String>,
+
+    /// Calls assigned to class attributes, by attribute name, e.g.,
+    /// `mapped_column(primary_key=True)` for `id`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub calls: BTreeMap<String, Call>,
+
+    pub docstring: Option<String>,
+
+    /// Dotted path of the module defining the class, if known.
+    pub module: String,
+
+    /// Path of the file defining the class, if known.
+    pub path: PathBuf,
+    pub location: Location,
+}
+
+/// The flavor of class, as far as UML is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassKind {
+    Class,
+    Abstract,
+    Interface,
+    Enum,
+}
+
+/// Where a dotted module path would live on disk, relative to the root of
+/// the search, e.g., `models.base` for `src/models/base.py`.
+///
+/// Leading directories that cannot be imported, such as `src` or an sdist's
+/// `pkg-1.0.0`, are skipped.
+pub fn module_name(path: &std::path::Path) -> String {
+    let mut parts = path
+        .with_extension("")
+        .components()
+        .filter_map(|c| c.as_os_str().to_str().map(str::to_string))
+        .collect::<Vec<_>>();
+    if parts.last().is_some_and(|p| p == "__init__") {
+        parts.pop();
+    }
+
+    let is_identifier = |p: &String| {
+        p.chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && p.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    let skip = parts
+        .iter()
+        .position(|p| is_identifier(p) && p != "src")
+        .unwrap_or(parts.len());
+    parts[skip..].join(".")
+}
+
+/// Truncates a dotted module path to its package at `depth`, e.g.,
+/// `pkg.models` for `pkg.models.base` at a depth of 2.
+pub fn package_of(module: &str, depth: Option<usize>) -> String {
+    match depth {
+        Some(depth) => module
+            .split('.')
+            .take(depth.max(1))
+            .collect::<Vec<_>>()
+            .join("."),
+        None => module.to_string(),
+    }
+}
+
+/// Byte offsets of the start of each line in a source, used to map AST
+/// ranges to line numbers.
+struct LineIndex(Vec<usize>);
+
+impl LineIndex {
+    fn new(src: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self(starts)
+    }
+
+    /// Returns the 1-indexed line containing the given byte offset.
+    fn line_of(&self, offset: usize) -> usize {
+        self.0.partition_point(|&start| start <= offset)
+    }
 }
 
 impl PyClassInfo {
     pub fn from_source(src: &str) -> Result<impl Iterator<Item = Result<Self>>> {
         let parsed = ast::Suite::parse(src, "path").map_err(|_| errors::ParseError::AstParse)?;
+        let lines = LineIndex::new(src);
 
-        let mapped = parsed.into_iter().filter_map(|node| match node {
-            ast::Stmt::ClassDef(stmt) => Some(PyClassInfo::try_from(stmt)),
+        let mapped = parsed.into_iter().filter_map(move |node| match node {
+            ast::Stmt::ClassDef(stmt) => {
+                let start = u32::from(stmt.range.start()) as usize;
+                let end = u32::from(stmt.range.end()) as usize;
+                let location = Location {
+                    line: lines.line_of(start),
+                    end_line: lines.line_of(end.saturating_sub(1)),
+                };
+                Some(PyClassInfo::try_from(stmt).map(|cls| PyClassInfo { location, ..cls }))
+            }
             _ => None,
         });
 
         Ok(mapped)
     }
 
+    /// The module-qualified name of the class, e.g., `models.base.Base`.
+    pub fn qualified_name(&self) -> String {
+        if self.module.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.module, self.name)
+        }
+    }
+
+    pub fn kind(&self) -> ClassKind {
+        let bases = self
+            .parents
+            .iter()
+            .map(|p| p.rsplit('.').next().unwrap_or(p))
+            .collect::<Vec<_>>();
+
+        if bases.iter().any(|b| ENUM_BASES.contains(b)) {
+            ClassKind::Enum
+        } else if bases.contains(&"Protocol") {
+            ClassKind::Interface
+        } else if bases.contains(&"ABC")
+            || self
+                .keywords
+                .get("metaclass")
+                .is_some_and(|m| m.ends_with("ABCMeta"))
+            || self.methods.iter().any(|m| m.is_abstract())
+        {
+            ClassKind::Abstract
+        } else {
+            ClassKind::Class
+        }
+    }
+
     fn get_class_name(cls: &ast::StmtClassDef) -> String {
         cls.name.to_string()
     }
 
+    /// Returns the name of a generic base, e.g., `Generic` for `Generic[T]`.
+    fn get_subscripted_name(base: &ast::Expr) -> Option<String> {
+        match base {
+            ast::Expr::Subscript(s) => s.value.print_annotation().ok(),
+            _ => None,
+        }
+    }
+
     fn get_parent_class_names(cls: &ast::StmtClassDef) -> Result<BTreeSet<String>> {
         cls.bases
             .iter()
-            .map(|base| match base {
-                ast::Expr::Name(name) => Ok(name.id.to_string()),
-                ast::Expr::Attribute(attr) => match attr.value.as_ref() {
-                    ast::Expr::Name(ast::ExprName { id, .. }) => Ok(format!("{id}.{}", attr.attr)),
-                    _ => Ok(attr.attr.to_string()),
-                },
-                _ => Err(errors::ParseError::ExprParse(base.clone())),
+            .filter_map(|base| {
+                // Only `Base` in `Base[T]` names a parent, and `Generic[T]`
+                // only declares type variables.
+                let base = match base {
+                    ast::Expr::Subscript(s) => s.value.as_ref(),
+                    _ => base,
+                };
+                match base {
+                    ast::Expr::Name(name) if name.id.as_str() == "Generic" => None,
+                    ast::Expr::Name(name) => Some(Ok(name.id.to_string())),
+                    ast::Expr::Attribute(attr) if attr.attr.as_str() == "Generic" => None,
+                    ast::Expr::Attribute(attr) => Some(Ok(match attr.value.as_ref() {
+                        ast::Expr::Name(ast::ExprName { id, .. }) => format!("{id}.{}", attr.attr),
+                        _ => attr.attr.to_string(),
+                    })),
+                    _ => Some(Err(errors::ParseError::ExprParse(base.clone()))),
+                }
             })
             .collect::<Result<_>>()
     }
 
+    /// Collects type variables from `Generic[...]` and `Protocol[...]` bases.
+    fn get_type_params(cls: &ast::StmtClassDef) -> Vec<String> {
+        let mut params = Vec::new();
+        for base in cls.bases.iter() {
+            let Some(name) = Self::get_subscripted_name(base) else {
+                continue;
+            };
+            if !matches!(name.rsplit('.').next(), Some("Generic" | "Protocol")) {
+                continue;
+            }
+            let ast::Expr::Subscript(s) = base else {
+                continue;
+            };
+            let args = match s.slice.as_ref() {
+                ast::Expr::Tuple(t) => t.elts.iter().collect::<Vec<_>>(),
+                slice => vec![slice],
+            };
+            for arg in args {
+                if let Ok(param) = arg.print_annotation() {
+                    if !params.contains(&param) {
+                        params.push(param);
+                    }
+                }
+            }
+        }
+        params
+    }
+
+    fn get_keywords(cls: &ast::StmtClassDef) -> BTreeMap<String, String> {
+        cls.keywords
+            .iter()
+            .filter_map(|k| {
+                let arg = k.arg.as_ref()?.to_string();
+                let value = k
+                    .value
+                    .print_value()
+                    .or_else(|| k.value.print_annotation().ok())?;
+                Some((arg, value))
+            })
+            .collect()
+    }
+
+    fn get_calls(cls: &ast::StmtClassDef) -> BTreeMap<String, Call> {
+        cls.body
+            .iter()
+            .filter_map(|node| {
+                let (target, value) = match node {
+                    ast::Stmt::Assign(a) => (a.targets.first()?, a.value.as_ref()),
+                    ast::Stmt::AnnAssign(a) => (a.target.as_ref(), a.value.as_deref()?),
+                    _ => return None,
+                };
+                match (target, value) {
+                    (ast::Expr::Name(n), ast::Expr::Call(c)) => {
+                        Some((n.id.to_string(), Call::from(c)))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
     fn get_fields_from_init(func: &ast::StmtFunctionDef) -> Vec<Field> {
         func.body
             .iter()
@@ -132,6 +349,14 @@ impl TryFrom<ast::StmtClassDef> for PyClassInfo {
     fn try_from(value: ast::StmtClassDef) -> Result<Self> {
         let name = Self::get_class_name(&value);
         let parents = Self::get_parent_class_names(&value)?;
+        let type_params = Self::get_type_params(&value);
+        let keywords = Self::get_keywords(&value);
+        let calls = Self::get_calls(&value);
+        let decorators = value
+            .decorator_list
+            .iter()
+            .filter_map(|d| d.print_decorator())
+            .collect();
         let (fields, methods) = Self::get_fields_and_methods(&value)?;
 
         Ok(PyClassInfo {
@@ -139,6 +364,14 @@ impl TryFrom<ast::StmtClassDef> for PyClassInfo {
             parents,
             fields,
             methods,
+            type_params,
+            decorators,
+            keywords,
+            calls,
+            docstring: get_docstring(&value.body),
+            module: String::new(),
+            path: PathBuf::new(),
+            location: Location::default(),
         })
     }
 }
@@ -172,6 +405,8 @@ mod test {
                         dtype: Some("str".to_string()),
                     }],
                     returns: None,
+                    decorators: vec![],
+                    docstring: None,
                 }
             );
         } else {
@@ -206,6 +441,8 @@ mod test {
                         }
                     ],
                     returns: Some("str".to_string()),
+                    decorators: vec![],
+                    docstring: None,
                 }
             )
         } else {
@@ -249,6 +486,38 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_parse_nested_annotation() {
+        let py = "x: Mapped[Optional[list['Order']]] = relationship(back_populates='user')";
+        if let ast::Stmt::AnnAssign(ref a) = get_stmt(py) {
+            let assignment = Field::try_from(a).unwrap();
+            assert_eq!(
+                assignment.dtype.as_deref(),
+                Some("Mapped[Optional[list['Order']]]")
+            );
+        } else {
+            panic!("failed to parse assignment");
+        }
+    }
+
+    #[test]
+    fn test_parse_str_assignment() {
+        let py = "__tablename__ = 'orders'";
+        if let ast::Stmt::Assign(ref a) = get_stmt(py) {
+            let assignment = Field::try_from(a).unwrap();
+            assert_eq!(
+                assignment,
+                Field {
+                    name: "__tablename__".to_string(),
+                    dtype: Some("str".to_string()),
+                    default: Some("orders".to_string()),
+                }
+            );
+        } else {
+            panic!("failed to parse assignment");
+        }
+    }
+
     #[test]
     fn test_parse_annotated_dict_assignment() {
         let py = "x: dict[str, tuple[int, ...]] = {'a': (1, 2), 'b': (2,), 'c': (3, 3, 3,)}";
@@ -354,4 +623,76 @@ mod test {
         }
         panic!("failed to parse class");
     }
+
+    #[test]
+    fn test_parse_generic_class() {
+        #[rustfmt::skip]
+        let py = [
+            "@dataclass(frozen=True)",
+            "class Box(Base, Generic[K, V], metaclass=ABCMeta):",
+            "    @abc.abstractmethod",
+            "    def get(self) -> V: ...",
+        ]
+        .join("\n");
+
+        if let ast::Stmt::ClassDef(c) = get_stmt(&py) {
+            let cls = PyClassInfo::try_from(c).unwrap();
+            assert_eq!(cls.parents, BTreeSet::from(["Base".to_string()]));
+            assert_eq!(cls.type_params, vec!["K".to_string(), "V".to_string()]);
+            assert_eq!(cls.decorators, vec!["dataclass".to_string()]);
+            assert_eq!(cls.keywords["metaclass"], "ABCMeta");
+            assert_eq!(cls.kind(), ClassKind::Abstract);
+            return;
+        }
+        panic!("failed to parse class");
+    }
+
+    #[test]
+    fn test_parse_docstring() {
+        #[rustfmt::skip]
+        let py = [
+            "class Order:",
+            r#"    """An order.
+
+        Placed by a customer.
+    """"#,
+            "    def total(self) -> int:",
+            r#"        """Sums the lines.""""#,
+            "        return 0",
+        ]
+        .join("\n");
+
+        if let ast::Stmt::ClassDef(c) = get_stmt(&py) {
+            let cls = PyClassInfo::try_from(c).unwrap();
+            assert_eq!(
+                cls.docstring.as_deref(),
+                Some("An order.\n\nPlaced by a customer.")
+            );
+            assert_eq!(
+                cls.methods.first().unwrap().docstring.as_deref(),
+                Some("Sums the lines.")
+            );
+            return;
+        }
+        panic!("failed to parse class");
+    }
+
+    #[test]
+    fn test_module_name() {
+        use std::path::Path;
+
+        assert_eq!(module_name(Path::new("src/models/base.py")), "models.base");
+        assert_eq!(module_name(Path::new("models/__init__.py")), "models");
+        assert_eq!(module_name(Path::new("pkg-1.0/src/pkg/a.py")), "pkg.a");
+        assert_eq!(module_name(Path::new("./a.py")), "a");
+    }
+
+    #[test]
+    fn test_line_index() {
+        let lines = LineIndex::new("a = 1\nclass A:\n    pass\n");
+        assert_eq!(lines.line_of(0), 1);
+        assert_eq!(lines.line_of(5), 1);
+        assert_eq!(lines.line_of(6), 2);
+        assert_eq!(lines.line_of(20), 3);
+    }
 }
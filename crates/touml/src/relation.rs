@@ -0,0 +1,312 @@
+use crate::python::{ClassKind, PyClassInfo};
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The kinds of relationship drawn between classes, as UML names them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelationKind {
+    /// Subclasses a base, e.g., `class Order(Base)`.
+    Inheritance,
+    /// Implements a protocol, e.g., `class Order(Serializable)`.
+    Realization,
+    /// Holds an instance outright, e.g., `customer: Customer`.
+    Composition,
+    /// Holds instances in a container, e.g., `lines: list[Line]`.
+    Aggregation,
+    /// Holds an optional instance, e.g., `parent: Optional[Node]`.
+    Association,
+    /// Only mentions a class in method signatures.
+    Dependency,
+}
+
+/// Either a class in the model, by its index, or a parent that could not be
+/// resolved to one, by its name as written, e.g., `pydantic.BaseModel`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    Class(usize),
+    External(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Relation {
+    pub from: usize,
+    pub to: Target,
+    pub kind: RelationKind,
+
+    /// The field the relationship comes from, if any.
+    pub label: Option<String>,
+}
+
+/// Resolves names as written in Python to classes in the model.
+///
+/// Bare names are matched on the class name, preferring a class in the same
+/// module. Dotted names, e.g., `models.Order`, only match a class whose
+/// qualified name ends with them, so that `pydantic.BaseModel` never resolves
+/// to a class of the project that happens to be named `BaseModel`.
+struct Resolver<'a> {
+    classes: &'a [PyClassInfo],
+    by_name: BTreeMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(classes: &'a [PyClassInfo]) -> Self {
+        let mut by_name: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (i, cls) in classes.iter().enumerate() {
+            by_name.entry(cls.name.as_str()).or_default().push(i);
+        }
+        Self { classes, by_name }
+    }
+
+    fn resolve(&self, name: &str, module: &str) -> Option<usize> {
+        let candidates = self.by_name.get(name.rsplit('.').next()?)?;
+        if !name.contains('.') {
+            return candidates
+                .iter()
+                .find(|&&i| self.classes[i].module == module)
+                .or_else(|| candidates.first())
+                .copied();
+        }
+        let suffix = format!(".{name}");
+        candidates
+            .iter()
+            .find(|&&i| {
+                let qualified = self.classes[i].qualified_name();
+                qualified == name || qualified.ends_with(&suffix)
+            })
+            .copied()
+    }
+}
+
+/// Splits a type annotation into the dotted names it mentions, e.g.,
+/// `dict[str, models.Order]` into `dict`, `str` and `models.Order`.
+fn names_in(annotation: &str) -> impl Iterator<Item = &str> {
+    annotation
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .filter(|s| !s.is_empty() && !s.starts_with(|c: char| c.is_numeric()))
+}
+
+/// How a field holds the class `name`, given its annotation.
+fn field_relation(annotation: &str, name: &str) -> RelationKind {
+    let annotation = annotation.replace(' ', "");
+    let optional = [
+        format!("Optional[{name}]"),
+        format!("typing.Optional[{name}]"),
+        format!("{name}|None"),
+        format!("None|{name}"),
+    ];
+    if annotation == name {
+        RelationKind::Composition
+    } else if optional.contains(&annotation) {
+        RelationKind::Association
+    } else {
+        RelationKind::Aggregation
+    }
+}
+
+/// Gathers every relationship between the given classes, from their bases,
+/// field annotations and method signatures.
+///
+/// Only inheritance may point outside of the model; fields and methods
+/// typed with unknown classes are not related to anything.
+pub fn relations(classes: &[PyClassInfo]) -> Vec<Relation> {
+    let resolver = Resolver::new(classes);
+    let mut result = Vec::new();
+
+    for (i, cls) in classes.iter().enumerate() {
+        for parent in cls.parents.iter() {
+            let (to, kind) = match resolver.resolve(parent, &cls.module) {
+                Some(j) if j == i => continue,
+                Some(j) if classes[j].kind() == ClassKind::Interface => {
+                    (Target::Class(j), RelationKind::Realization)
+                }
+                Some(j) => (Target::Class(j), RelationKind::Inheritance),
+                None => (Target::External(parent.clone()), RelationKind::Inheritance),
+            };
+            result.push(Relation {
+                from: i,
+                to,
+                kind,
+                label: None,
+            });
+        }
+
+        let mut held = BTreeSet::new();
+        for field in cls.fields.iter() {
+            let Some(dtype) = &field.dtype else {
+                continue;
+            };
+            let mut seen = BTreeSet::new();
+            for name in names_in(dtype) {
+                let Some(j) = resolver.resolve(name, &cls.module) else {
+                    continue;
+                };
+                if !seen.insert(j) {
+                    continue;
+                }
+                held.insert(j);
+                result.push(Relation {
+                    from: i,
+                    to: Target::Class(j),
+                    kind: field_relation(dtype, name),
+                    label: Some(field.name.clone()),
+                });
+            }
+        }
+
+        let mut used = BTreeSet::new();
+        for method in cls.methods.iter() {
+            let annotations = method
+                .args
+                .iter()
+                .filter_map(|a| a.dtype.as_deref())
+                .chain(method.returns.as_deref());
+            for name in annotations.flat_map(names_in) {
+                if let Some(j) = resolver.resolve(name, &cls.module) {
+                    if j != i && !held.contains(&j) {
+                        used.insert(j);
+                    }
+                }
+            }
+        }
+        for j in used {
+            result.push(Relation {
+                from: i,
+                to: Target::Class(j),
+                kind: RelationKind::Dependency,
+                label: None,
+            });
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn field(name: &str, dtype: &str) -> Field {
+        Field {
+            name: name.to_string(),
+            dtype: Some(dtype.to_string()),
+            default: None,
+        }
+    }
+
+    #[test]
+    fn test_relations() {
+        let classes = [
+            PyClassInfo {
+                name: "Serializable".to_string(),
+                parents: BTreeSet::from(["Protocol".to_string()]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Order".to_string(),
+                parents: BTreeSet::from([
+                    "Serializable".to_string(),
+                    "pydantic.BaseModel".to_string(),
+                ]),
+                fields: BTreeSet::from([
+                    field("customer", "Customer"),
+                    field("lines", "list[Line]"),
+                    field("parent", "Optional[Order]"),
+                ]),
+                methods: BTreeSet::from([Method {
+                    name: "ship".to_string(),
+                    args: vec![field("to", "Customer"), field("via", "Carrier")],
+                    returns: None,
+                    decorators: vec![],
+                    docstring: None,
+                }]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Customer".to_string(),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Line".to_string(),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Carrier".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let relation = |to, kind, label: Option<&str>| Relation {
+            from: 1,
+            to,
+            kind,
+            label: label.map(str::to_string),
+        };
+        assert_eq!(
+            relations(&classes)
+                .into_iter()
+                .filter(|r| r.from == 1)
+                .collect::<Vec<_>>(),
+            vec![
+                relation(Target::Class(0), RelationKind::Realization, None),
+                relation(
+                    Target::External("pydantic.BaseModel".to_string()),
+                    RelationKind::Inheritance,
+                    None
+                ),
+                relation(
+                    Target::Class(2),
+                    RelationKind::Composition,
+                    Some("customer")
+                ),
+                relation(Target::Class(3), RelationKind::Aggregation, Some("lines")),
+                relation(Target::Class(1), RelationKind::Association, Some("parent")),
+                relation(Target::Class(4), RelationKind::Dependency, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relations_dotted_parents() {
+        let class = |name: &str, module: &str, parents: &[&str]| PyClassInfo {
+            name: name.to_string(),
+            module: module.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        let classes = [
+            class("BaseModel", "shop.base", &[]),
+            class("Order", "shop.mymodels", &[]),
+            class(
+                "Item",
+                "shop.items",
+                &["pydantic.BaseModel", "base.BaseModel"],
+            ),
+            class("Line", "shop.items", &["models.Order", "BaseModel"]),
+        ];
+
+        let parents = |from| {
+            relations(&classes)
+                .into_iter()
+                .filter(|r| r.from == from)
+                .map(|r| r.to)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            parents(2),
+            [
+                Target::Class(0),
+                Target::External("pydantic.BaseModel".to_string())
+            ]
+        );
+        assert_eq!(
+            parents(3),
+            [
+                Target::Class(0),
+                Target::External("models.Order".to_string())
+            ]
+        );
+    }
+}
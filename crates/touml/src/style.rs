@@ -0,0 +1,285 @@
+use crate::mermaid::escape;
+use crate::prelude::*;
+use crate::python::*;
+use crate::relation::*;
+
+use serde::{de, Deserialize, Deserializer};
+use std::collections::BTreeSet;
+
+static INDENT: &str = "    ";
+
+/// Styles the classes matching every given selector with a Mermaid `classDef`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StyleRule {
+    /// The name of the `classDef`, e.g., `pydantic`.
+    pub name: String,
+    /// Its CSS, e.g., `fill:#cde4ff,stroke:#1f5fbf`.
+    pub style: String,
+    /// A glob over the dotted path of the module, e.g., `pkg.models.*`.
+    pub module: Option<Glob>,
+    /// A glob over the names of any ancestor, e.g., `BaseModel` or
+    /// `pydantic.*`.
+    pub base: Option<Glob>,
+    pub stereotype: Option<ClassKind>,
+    /// Whether to match parents outside of the model instead of classes.
+    #[serde(default)]
+    pub external: bool,
+    /// What the legend says about the rule, instead of its selectors.
+    pub label: Option<String>,
+}
+
+/// A glob pattern, compiled and checked once when the rule is read.
+#[derive(Debug, Clone)]
+pub struct Glob(glob::Pattern);
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Self, glob::PatternError> {
+        glob::Pattern::new(pattern).map(Self)
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        self.0.matches(text)
+    }
+
+    /// Matches a class name with or without the module, e.g., `BaseModel`
+    /// for `pydantic.BaseModel`.
+    fn matches_class(&self, name: &str) -> bool {
+        self.matches(name) || name.rsplit_once('.').is_some_and(|(_, n)| self.matches(n))
+    }
+}
+
+impl<'de> Deserialize<'de> for Glob {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern)
+            .map_err(|e| de::Error::custom(format!("invalid pattern `{pattern}`: {e}")))
+    }
+}
+
+impl StyleRule {
+    fn matches_class(&self, cls: &PyClassInfo, ancestors: &BTreeSet<String>) -> bool {
+        !self.external
+            && self.module.as_ref().is_none_or(|m| m.matches(&cls.module))
+            && self
+                .base
+                .as_ref()
+                .is_none_or(|b| ancestors.iter().any(|a| b.matches_class(a)))
+            && self.stereotype.is_none_or(|s| s == cls.kind())
+    }
+
+    /// Matches a parent outside of the model, e.g., `pydantic.BaseModel`,
+    /// by the module it appears to come from.
+    fn matches_external(&self, name: &str) -> bool {
+        let module = name.rsplit_once('.').map_or("", |(m, _)| m);
+        self.external
+            && self.base.is_none()
+            && self.stereotype.is_none()
+            && self.module.as_ref().is_none_or(|m| m.matches(module))
+    }
+
+    fn describe(&self) -> String {
+        if let Some(label) = &self.label {
+            return label.clone();
+        }
+        let mut parts = Vec::new();
+        if let Some(stereotype) = self.stereotype {
+            parts.push(
+                match stereotype {
+                    ClassKind::Class => "concrete classes",
+                    ClassKind::Abstract => "abstract classes",
+                    ClassKind::Interface => "interfaces",
+                    ClassKind::Enum => "enumerations",
+                }
+                .to_string(),
+            );
+        }
+        if self.external {
+            parts.push("classes outside of the model".to_string());
+        }
+        if let Some(base) = &self.base {
+            parts.push(format!("subclasses of {}", base.0.as_str()));
+        }
+        if let Some(module) = &self.module {
+            parts.push(format!("in {}", module.0.as_str()));
+        }
+        match parts.is_empty() {
+            true => "all classes".to_string(),
+            false => parts.join(", "),
+        }
+    }
+}
+
+/// Collects the names of every ancestor of each class, as written in the
+/// source as well as qualified by module where the ancestor is in the model.
+fn ancestors(classes: &[PyClassInfo]) -> Vec<BTreeSet<String>> {
+    let mut parents = vec![Vec::new(); classes.len()];
+    let mut names = vec![BTreeSet::new(); classes.len()];
+    for relation in relations(classes) {
+        if !matches!(
+            relation.kind,
+            RelationKind::Inheritance | RelationKind::Realization
+        ) {
+            continue;
+        }
+        match relation.to {
+            Target::Class(j) => parents[relation.from].push(j),
+            Target::External(name) => {
+                names[relation.from].insert(name);
+            }
+        }
+    }
+
+    (0..classes.len())
+        .map(|i| {
+            let mut result = names[i].clone();
+            let mut seen = BTreeSet::from([i]);
+            let mut stack = parents[i].clone();
+            while let Some(j) = stack.pop() {
+                if !seen.insert(j) {
+                    continue;
+                }
+                let cls = &classes[j];
+                result.insert(cls.name.clone());
+                if !cls.module.is_empty() {
+                    result.insert(format!("{}.{}", cls.module, cls.name));
+                }
+                result.extend(names[j].iter().cloned());
+                stack.extend(parents[j].iter().copied());
+            }
+            result
+        })
+        .collect()
+}
+
+/// Defines a `classDef` per rule, assigns it to the nodes it matches, and
+/// explains the rules in a note. Rules matching nothing are left out.
+///
+/// Takes the node ID of each class, and the name and node ID of each parent
+/// outside of the model.
+pub(crate) fn print_styles(
+    rules: &[StyleRule],
+    classes: &[PyClassInfo],
+    ids: &[String],
+    externals: &[(String, String)],
+) -> Option<String> {
+    let ancestors = ancestors(classes);
+
+    let mut definitions = String::new();
+    let mut legend = Vec::new();
+    for rule in rules {
+        let nodes = classes
+            .iter()
+            .zip(ids)
+            .zip(&ancestors)
+            .filter(|((cls, _), ancestors)| rule.matches_class(cls, ancestors))
+            .map(|((_, id), _)| id.as_str())
+            .chain(
+                externals
+                    .iter()
+                    .filter(|(name, _)| rule.matches_external(name))
+                    .map(|(_, id)| id.as_str()),
+            )
+            .collect::<Vec<_>>();
+        if nodes.is_empty() {
+            continue;
+        }
+        let name = rule
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        definitions.push_str(&format!("{INDENT}classDef {name} {}{EOL}", rule.style));
+        definitions.push_str(&format!(
+            "{INDENT}cssClass \"{}\" {name}{EOL}",
+            nodes.join(",")
+        ));
+        legend.push(format!("{name}: {}", escape(&rule.describe())));
+    }
+
+    if legend.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{definitions}{EOL}{INDENT}note \"Legend\\n{}\"{EOL}",
+        legend.join("\\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_print_styles() {
+        let class = |name: &str, module: &str, parent: &str| PyClassInfo {
+            name: name.to_string(),
+            module: module.to_string(),
+            parents: BTreeSet::from([parent.to_string()]),
+            ..Default::default()
+        };
+        let classes = [
+            class("Base", "pkg.models", "pydantic.BaseModel"),
+            class("Order", "pkg.models", "Base"),
+            class("OrderError", "pkg.errors", "Exception"),
+            class("Repository", "pkg.db", "ABC"),
+        ];
+        let ids = classes.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        let externals = [
+            (
+                "pydantic.BaseModel".to_string(),
+                "pydantic_BaseModel".to_string(),
+            ),
+            ("Exception".to_string(), "Exception".to_string()),
+            ("ABC".to_string(), "ABC".to_string()),
+        ];
+        let rule = |name: &str, style: &str| StyleRule {
+            name: name.to_string(),
+            style: style.to_string(),
+            ..Default::default()
+        };
+        let rules = [
+            StyleRule {
+                base: Some(Glob::new("BaseModel").unwrap()),
+                ..rule("pydantic", "fill:#cde4ff")
+            },
+            StyleRule {
+                base: Some(Glob::new("*Exception").unwrap()),
+                label: Some("Errors".to_string()),
+                ..rule("error", "fill:#fdd")
+            },
+            StyleRule {
+                stereotype: Some(ClassKind::Abstract),
+                ..rule("abstract", "stroke-dasharray:5 5")
+            },
+            StyleRule {
+                external: true,
+                module: Some(Glob::new("pydantic").unwrap()),
+                ..rule("external", "fill:#eee")
+            },
+            StyleRule {
+                module: Some(Glob::new("other.*").unwrap()),
+                ..rule("unused", "fill:#000")
+            },
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            "    classDef pydantic fill:#cde4ff",
+            "    cssClass \"Base,Order\" pydantic",
+            "    classDef error fill:#fdd",
+            "    cssClass \"OrderError\" error",
+            "    classDef abstract stroke-dasharray:5 5",
+            "    cssClass \"Repository\" abstract",
+            "    classDef external fill:#eee",
+            "    cssClass \"pydantic_BaseModel\" external",
+            "",
+            "    note \"Legend\\npydantic: subclasses of BaseModel\\nerror: Errors\\nabstract: abstract classes\\nexternal: classes outside of the model, in pydantic\"",
+            "",
+        ].join(EOL);
+        assert_eq!(
+            print_styles(&rules, &classes, &ids, &externals).unwrap(),
+            expected
+        );
+    }
+}
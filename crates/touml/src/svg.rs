@@ -0,0 +1,342 @@
+use crate::layout::*;
+use crate::prelude::*;
+use crate::python::*;
+use crate::relation::*;
+
+use std::collections::BTreeMap;
+
+static INDENT: &str = "  ";
+/// Advance of a character of the monospace font, at [`FONT_SIZE`].
+static CHAR_WIDTH: f64 = 7.2;
+static FONT_SIZE: f64 = 12.0;
+static LINE_HEIGHT: f64 = 16.0;
+static PADDING: f64 = 6.0;
+static MARGIN: f64 = 20.0;
+
+static DEFS: &str = r##"<defs>
+    <marker id="inheritance" viewBox="0 0 12 12" refX="12" refY="6" markerWidth="12" markerHeight="12" orient="auto">
+      <path d="M0,0 L12,6 L0,12 z" fill="#fff" stroke="#333"/>
+    </marker>
+    <marker id="composition" viewBox="0 0 16 12" refX="16" refY="6" markerWidth="16" markerHeight="12" orient="auto-start-reverse">
+      <path d="M0,6 L8,0 L16,6 L8,12 z" fill="#333" stroke="#333"/>
+    </marker>
+    <marker id="aggregation" viewBox="0 0 16 12" refX="16" refY="6" markerWidth="16" markerHeight="12" orient="auto-start-reverse">
+      <path d="M0,6 L8,0 L16,6 L8,12 z" fill="#fff" stroke="#333"/>
+    </marker>
+    <marker id="association" viewBox="0 0 12 12" refX="12" refY="6" markerWidth="12" markerHeight="12" orient="auto">
+      <path d="M0,0 L12,6 L0,12" fill="none" stroke="#333"/>
+    </marker>
+  </defs>"##;
+
+/// Attributes of the line drawn for each kind of relationship, following
+/// UML: diamonds sit at the owner and arrowheads at the target.
+fn edge_attrs(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Inheritance => r#"marker-end="url(#inheritance)""#,
+        RelationKind::Realization => r#"marker-end="url(#inheritance)" stroke-dasharray="6,4""#,
+        RelationKind::Composition => r#"marker-start="url(#composition)""#,
+        RelationKind::Aggregation => r#"marker-start="url(#aggregation)""#,
+        RelationKind::Association => r#"marker-end="url(#association)""#,
+        RelationKind::Dependency => r#"marker-end="url(#association)" stroke-dasharray="6,4""#,
+    }
+}
+
+/// A line of text within a box, with its SVG style.
+struct Line {
+    text: String,
+    style: &'static str,
+}
+
+/// A box with a compartment each for its name, fields and methods, or just
+/// a name for classes outside of the model.
+struct Node {
+    id: String,
+    title: Vec<Line>,
+    compartments: Vec<Vec<Line>>,
+    external: bool,
+}
+
+impl Node {
+    fn from_class(cls: &PyClassInfo) -> Self {
+        let kind = cls.kind();
+        let mut name = cls.name.clone();
+        if !cls.type_params.is_empty() {
+            name.push_str(&format!("[{}]", cls.type_params.join(", ")));
+        }
+        let mut title = Vec::new();
+        match kind {
+            ClassKind::Interface => title.push(Line {
+                text: "«interface»".to_string(),
+                style: "",
+            }),
+            ClassKind::Enum => title.push(Line {
+                text: "«enumeration»".to_string(),
+                style: "",
+            }),
+            _ => {}
+        }
+        title.push(Line {
+            text: name,
+            style: match kind {
+                ClassKind::Abstract => r#" font-weight="bold" font-style="italic""#,
+                _ => r#" font-weight="bold""#,
+            },
+        });
+
+        let fields = cls
+            .fields
+            .iter()
+            .filter(|f| !f.is_dunder())
+            .map(|f| match kind {
+                ClassKind::Enum => Line {
+                    text: f.name.clone(),
+                    style: "",
+                },
+                _ => Line {
+                    text: format!("{} {f}", get_access_modifier(f.is_public())),
+                    style: match f.is_static() {
+                        true => r#" text-decoration="underline""#,
+                        false => "",
+                    },
+                },
+            })
+            .collect();
+        let methods = cls
+            .methods
+            .iter()
+            .filter(|m| !m.is_dunder())
+            .map(|m| Line {
+                text: format!("{} {m}", get_access_modifier(m.is_public())),
+                style: match (m.is_static(), m.is_abstract()) {
+                    (true, _) => r#" text-decoration="underline""#,
+                    (_, true) => r#" font-style="italic""#,
+                    _ => "",
+                },
+            })
+            .collect();
+
+        Self {
+            id: cls.qualified_name(),
+            title,
+            compartments: vec![fields, methods],
+            external: false,
+        }
+    }
+
+    fn external(name: &str) -> Self {
+        Self {
+            id: name.to_string(),
+            title: vec![Line {
+                text: name.to_string(),
+                style: "",
+            }],
+            compartments: vec![],
+            external: true,
+        }
+    }
+
+    fn size(&self) -> (f64, f64) {
+        let chars = self
+            .title
+            .iter()
+            .chain(self.compartments.iter().flatten())
+            .map(|l| l.text.chars().count())
+            .max()
+            .unwrap_or(0);
+        let lines = self.title.len() + self.compartments.iter().map(|c| c.len()).sum::<usize>();
+        let width = (chars as f64 * CHAR_WIDTH + 2.0 * PADDING).max(60.0);
+        let height = lines as f64 * LINE_HEIGHT + (1 + self.compartments.len()) as f64 * PADDING;
+        (width, height)
+    }
+
+    fn render(&self, rect: &Rect, indent: &str) -> String {
+        let mut result = format!("{indent}<g id=\"{}\">{EOL}", escape_xml(&self.id));
+        let dash = match self.external {
+            true => r#" stroke-dasharray="4,3""#,
+            false => "",
+        };
+        result.push_str(&format!(
+            "{indent}{INDENT}<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#fffff4\" stroke=\"#333\"{dash}/>{EOL}",
+            rect.x, rect.y, rect.width, rect.height
+        ));
+
+        let baseline = |y: f64| y + (LINE_HEIGHT + FONT_SIZE) / 2.0 - 2.0;
+        let mut y = rect.y + PADDING / 2.0;
+        for line in self.title.iter() {
+            result.push_str(&format!(
+                "{indent}{INDENT}<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\"{}>{}</text>{EOL}",
+                rect.x + rect.width / 2.0,
+                baseline(y),
+                line.style,
+                escape_xml(&line.text)
+            ));
+            y += LINE_HEIGHT;
+        }
+        y += PADDING / 2.0;
+        for compartment in self.compartments.iter() {
+            result.push_str(&format!(
+                "{indent}{INDENT}<line x1=\"{:.1}\" y1=\"{y:.1}\" x2=\"{:.1}\" y2=\"{y:.1}\" stroke=\"#333\"/>{EOL}",
+                rect.x,
+                rect.x + rect.width
+            ));
+            y += PADDING / 2.0;
+            for line in compartment.iter() {
+                result.push_str(&format!(
+                    "{indent}{INDENT}<text x=\"{:.1}\" y=\"{:.1}\"{}>{}</text>{EOL}",
+                    rect.x + PADDING,
+                    baseline(y),
+                    line.style,
+                    escape_xml(&line.text)
+                ));
+                y += LINE_HEIGHT;
+            }
+            y += PADDING / 2.0;
+        }
+        result.push_str(&format!("{indent}</g>{EOL}"));
+        result
+    }
+}
+
+fn points(route: &[Point]) -> String {
+    route
+        .iter()
+        .map(|p| format!("{:.1},{:.1}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Renders classes as a standalone SVG image of a UML class diagram.
+///
+/// The inheritance hierarchy is laid out in layers, with parents above their
+/// children. Other relationships are drawn as straight lines between boxes.
+pub fn classes_to_svg(classes: &[PyClassInfo]) -> String {
+    let relations = relations(classes);
+
+    let mut nodes = classes.iter().map(Node::from_class).collect::<Vec<_>>();
+    let mut externals = BTreeMap::new();
+    let mut node_of = |target: &Target| match target {
+        Target::Class(i) => *i,
+        Target::External(name) => *externals.entry(name.clone()).or_insert_with(|| {
+            nodes.push(Node::external(name));
+            nodes.len() - 1
+        }),
+    };
+    let ends = relations
+        .iter()
+        .map(|r| (r.from, node_of(&r.to)))
+        .collect::<Vec<_>>();
+
+    let hierarchy = relations
+        .iter()
+        .zip(ends.iter())
+        .filter(|(r, _)| {
+            matches!(
+                r.kind,
+                RelationKind::Inheritance | RelationKind::Realization
+            )
+        })
+        .collect::<Vec<_>>();
+    let sizes = nodes.iter().map(Node::size).collect::<Vec<_>>();
+    let layout = layered(
+        &sizes,
+        &hierarchy.iter().map(|(_, &e)| e).collect::<Vec<_>>(),
+    );
+
+    let (width, height) = (layout.width + 2.0 * MARGIN, layout.height + 2.0 * MARGIN);
+    let mut result = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}" font-family="monospace" font-size="{FONT_SIZE}">{EOL}"#
+    );
+    result.push_str(&format!("{INDENT}{DEFS}{EOL}"));
+    result.push_str(&format!(
+        "{INDENT}<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>{EOL}"
+    ));
+    result.push_str(&format!(
+        "{INDENT}<g transform=\"translate({MARGIN},{MARGIN})\">{EOL}"
+    ));
+
+    let indent = INDENT.repeat(2);
+    for ((relation, _), route) in hierarchy.iter().zip(layout.edges.iter()) {
+        result.push_str(&format!(
+            "{indent}<polyline points=\"{}\" fill=\"none\" stroke=\"#333\" {}/>{EOL}",
+            points(route),
+            edge_attrs(relation.kind)
+        ));
+    }
+    for (relation, &(from, to)) in relations.iter().zip(ends.iter()) {
+        if matches!(
+            relation.kind,
+            RelationKind::Inheritance | RelationKind::Realization
+        ) || from == to
+        {
+            continue;
+        }
+        let (a, b) = (layout.nodes[from], layout.nodes[to]);
+        let start = a.clip(b.center());
+        let end = b.clip(a.center());
+        result.push_str(&format!(
+            "{indent}<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#333\" {}/>{EOL}",
+            start.x,
+            start.y,
+            end.x,
+            end.y,
+            edge_attrs(relation.kind)
+        ));
+        if let Some(label) = &relation.label {
+            result.push_str(&format!(
+                "{indent}<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" fill=\"#555\">{}</text>{EOL}",
+                (start.x + end.x) / 2.0 + 4.0,
+                (start.y + end.y) / 2.0 - 4.0,
+                escape_xml(label)
+            ));
+        }
+    }
+
+    for (node, rect) in nodes.iter().zip(layout.nodes.iter()) {
+        result.push_str(&node.render(rect, &indent));
+    }
+
+    result.push_str(&format!("{INDENT}</g>{EOL}"));
+    result.push_str(&format!("</svg>{EOL}"));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_svg() {
+        let classes = [
+            PyClassInfo {
+                name: "Customer".to_string(),
+                parents: BTreeSet::from(["Base".to_string()]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Order".to_string(),
+                parents: BTreeSet::from(["Base".to_string()]),
+                fields: BTreeSet::from([Field {
+                    name: "customer".to_string(),
+                    dtype: Some("Customer".to_string()),
+                    default: None,
+                }]),
+                ..Default::default()
+            },
+        ];
+
+        let svg = classes_to_svg(&classes);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg""#));
+        assert!(svg.ends_with(&format!("</svg>{EOL}")));
+
+        // The external base is a dashed stub above both classes.
+        assert!(svg.contains(r#"<g id="Base">"#));
+        assert!(svg.contains(r#"stroke-dasharray="4,3"/>"#));
+        assert_eq!(svg.matches(r#"marker-end="url(#inheritance)""#).count(), 2);
+
+        // Fields are listed with their type, and a composition links the two.
+        assert!(svg.contains(">+ customer: Customer</text>"));
+        assert!(svg.contains(r#"marker-start="url(#composition)""#));
+        assert!(svg.contains(">customer</text>"));
+    }
+}
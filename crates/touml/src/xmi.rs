@@ -0,0 +1,559 @@
+use crate::prelude::*;
+use crate::python::*;
+use crate::relation::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+static INDENT: &str = "  ";
+
+fn visibility(is_public: bool) -> &'static str {
+    match is_public {
+        true => "public",
+        false => "private",
+    }
+}
+
+fn element_type(kind: ClassKind) -> &'static str {
+    match kind {
+        ClassKind::Class | ClassKind::Abstract => "uml:Class",
+        ClassKind::Interface => "uml:Interface",
+        ClassKind::Enum => "uml:Enumeration",
+    }
+}
+
+/// Assigns `xmi:id`s to classes and to the types their members mention.
+///
+/// Python annotations have no counterpart in UML, so each distinct one that
+/// is not a class in the model becomes a `uml:DataType` of that name.
+struct Ids<'a> {
+    classes: &'a [PyClassInfo],
+    externals: BTreeMap<String, String>,
+    types: BTreeMap<String, String>,
+}
+
+impl<'a> Ids<'a> {
+    fn new(classes: &'a [PyClassInfo], relations: &[Relation]) -> Self {
+        let externals = relations
+            .iter()
+            .filter_map(|r| match &r.to {
+                Target::External(name) => Some(name.clone()),
+                Target::Class(_) => None,
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|name| (name.clone(), format!("E.{name}")))
+            .collect();
+
+        let mut types = BTreeMap::new();
+        let annotations = classes.iter().flat_map(|cls| {
+            let fields = cls.fields.iter().filter_map(|f| f.dtype.clone());
+            let methods = cls.methods.iter().flat_map(|m| {
+                m.args
+                    .iter()
+                    .filter_map(|a| a.dtype.clone())
+                    .chain(m.returns.clone())
+            });
+            fields.chain(methods)
+        });
+        for annotation in annotations {
+            if classes.iter().any(|c| c.name == annotation) {
+                continue;
+            }
+            let id = format!("T{}", types.len() + 1);
+            types.entry(annotation).or_insert(id);
+        }
+
+        Self {
+            classes,
+            externals,
+            types,
+        }
+    }
+
+    fn class(&self, i: usize) -> String {
+        format!("C.{}", self.classes[i].qualified_name())
+    }
+
+    fn target(&self, target: &Target) -> String {
+        match target {
+            Target::Class(j) => self.class(*j),
+            Target::External(name) => self.externals[name].clone(),
+        }
+    }
+
+    /// The type of a member, preferring a class of the same name.
+    fn of_type(&self, annotation: &str) -> String {
+        match self.classes.iter().position(|c| c.name == annotation) {
+            Some(i) => self.class(i),
+            None => self.types[annotation].clone(),
+        }
+    }
+}
+
+/// Packages nested by their dotted path, holding the indices of classes.
+#[derive(Default)]
+struct Package<'a> {
+    classes: Vec<usize>,
+    children: BTreeMap<&'a str, Package<'a>>,
+}
+
+struct Writer<'a> {
+    ids: Ids<'a>,
+    classes: &'a [PyClassInfo],
+    relations: &'a [Relation],
+    lines: Vec<String>,
+}
+
+impl<'a> Writer<'a> {
+    fn line(&mut self, depth: usize, text: String) {
+        self.lines.push(format!("{}{text}", INDENT.repeat(depth)));
+    }
+
+    /// The ID of the attribute at the navigable end of an association. A field
+    /// holding several classes, e.g., `a: A | B`, has an attribute per class,
+    /// numbered after the first.
+    fn end(&self, n: usize) -> String {
+        let relation = &self.relations[n];
+        let k = self.relations[..n]
+            .iter()
+            .filter(|r| r.from == relation.from && r.label == relation.label)
+            .count();
+        let id = format!(
+            "{}.attr.{}",
+            self.ids.class(relation.from),
+            relation.label.as_deref().unwrap_or_default()
+        );
+        match k {
+            0 => id,
+            _ => format!("{id}.{}", k + 1),
+        }
+    }
+
+    fn typed(&self, annotation: Option<&str>) -> String {
+        annotation
+            .map(|a| format!(r#" type="{}""#, escape_xml(&self.ids.of_type(a))))
+            .unwrap_or_default()
+    }
+
+    fn package(&mut self, depth: usize, path: &str, package: &Package) {
+        for &i in package.classes.iter() {
+            self.class(depth, i);
+        }
+        for (name, child) in package.children.iter() {
+            let path = match path.is_empty() {
+                true => name.to_string(),
+                false => format!("{path}.{name}"),
+            };
+            self.line(
+                depth,
+                format!(
+                    r#"<packagedElement xmi:type="uml:Package" xmi:id="P.{}" name="{}">"#,
+                    escape_xml(&path),
+                    escape_xml(name)
+                ),
+            );
+            self.package(depth + 1, &path, child);
+            self.line(depth, "</packagedElement>".to_string());
+        }
+    }
+
+    fn class(&mut self, depth: usize, i: usize) {
+        let cls = &self.classes[i];
+        let id = self.ids.class(i);
+        let kind = cls.kind();
+        self.line(
+            depth,
+            format!(
+                r#"<packagedElement xmi:type="{}" xmi:id="{}" name="{}" isAbstract="{}">"#,
+                element_type(kind),
+                escape_xml(&id),
+                escape_xml(&cls.name),
+                kind == ClassKind::Abstract || kind == ClassKind::Interface
+            ),
+        );
+
+        if let Some(doc) = &cls.docstring {
+            self.line(
+                depth + 1,
+                format!(
+                    r#"<ownedComment xmi:type="uml:Comment" xmi:id="{}.comment" body="{}"/>"#,
+                    escape_xml(&id),
+                    escape_xml(doc)
+                ),
+            );
+        }
+
+        for (n, param) in cls.type_params.iter().enumerate() {
+            if n == 0 {
+                self.line(
+                    depth + 1,
+                    format!(
+                        r#"<ownedTemplateSignature xmi:type="uml:RedefinableTemplateSignature" xmi:id="{}.sig">"#,
+                        escape_xml(&id)
+                    ),
+                );
+            }
+            self.line(
+                depth + 2,
+                format!(
+                    r#"<ownedParameter xmi:type="uml:ClassifierTemplateParameter" xmi:id="{}.sig.{}"><ownedParameteredElement xmi:type="uml:Class" xmi:id="{}.sig.{}.class" name="{}"/></ownedParameter>"#,
+                    escape_xml(&id),
+                    escape_xml(param),
+                    escape_xml(&id),
+                    escape_xml(param),
+                    escape_xml(param)
+                ),
+            );
+            if n + 1 == cls.type_params.len() {
+                self.line(depth + 1, "</ownedTemplateSignature>".to_string());
+            }
+        }
+
+        let relations = self
+            .relations
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.from == i)
+            .collect::<Vec<_>>();
+        for (n, relation) in relations.iter() {
+            let general = escape_xml(&self.ids.target(&relation.to));
+            match relation.kind {
+                RelationKind::Inheritance => self.line(
+                    depth + 1,
+                    format!(
+                        r#"<generalization xmi:type="uml:Generalization" xmi:id="R{n}" general="{general}"/>"#
+                    ),
+                ),
+                RelationKind::Realization => self.line(
+                    depth + 1,
+                    format!(
+                        r#"<interfaceRealization xmi:type="uml:InterfaceRealization" xmi:id="R{n}" contract="{general}" implementingClassifier="{}"/>"#,
+                        escape_xml(&id)
+                    ),
+                ),
+                _ => continue,
+            }
+        }
+
+        // Fields that hold other classes are the navigable ends of associations.
+        let mut ends: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (n, relation) in relations.iter() {
+            if let Some(label) = relation.label.as_deref() {
+                ends.entry(label).or_default().push(*n);
+            }
+        }
+        // A field may be declared more than once, e.g., on the class and in
+        // `__init__`, but becomes a single attribute.
+        let fields = cls
+            .fields
+            .iter()
+            .filter(|f| !f.is_dunder())
+            .map(|f| (f.name.as_str(), f))
+            .collect::<BTreeMap<_, _>>();
+        for field in fields.into_values() {
+            let field_id = escape_xml(&format!("{id}.attr.{}", field.name));
+            if kind == ClassKind::Enum {
+                self.line(
+                    depth + 1,
+                    format!(
+                        r#"<ownedLiteral xmi:type="uml:EnumerationLiteral" xmi:id="{field_id}" name="{}"/>"#,
+                        escape_xml(&field.name)
+                    ),
+                );
+                continue;
+            }
+
+            let attrs = |field_id: &str| {
+                format!(
+                    r#"xmi:type="uml:Property" xmi:id="{field_id}" name="{}" visibility="{}" isStatic="{}""#,
+                    escape_xml(&field.name),
+                    visibility(field.is_public()),
+                    field.is_static()
+                )
+            };
+            let Some(ends) = ends.get(field.name.as_str()) else {
+                let attrs = attrs(&field_id) + &self.typed(field.dtype.as_deref());
+                self.line(depth + 1, format!("<ownedAttribute {attrs}/>"));
+                continue;
+            };
+            for &n in ends.iter() {
+                let relation = &self.relations[n];
+                let field_id = escape_xml(&self.end(n));
+                let aggregation = match relation.kind {
+                    RelationKind::Composition => "composite",
+                    RelationKind::Aggregation => "shared",
+                    _ => "none",
+                };
+                let attrs = attrs(&field_id)
+                    + &format!(
+                        r#" type="{}" aggregation="{aggregation}" association="R{n}""#,
+                        escape_xml(&self.ids.target(&relation.to))
+                    );
+                let (lower, upper) = match relation.kind {
+                    RelationKind::Composition => ("1", "1"),
+                    RelationKind::Aggregation => ("0", "*"),
+                    _ => ("0", "1"),
+                };
+                self.line(depth + 1, format!("<ownedAttribute {attrs}>"));
+                self.line(
+                    depth + 2,
+                    format!(
+                        r#"<lowerValue xmi:type="uml:LiteralInteger" xmi:id="{field_id}.lower" value="{lower}"/>"#
+                    ),
+                );
+                self.line(
+                    depth + 2,
+                    format!(
+                        r#"<upperValue xmi:type="uml:LiteralUnlimitedNatural" xmi:id="{field_id}.upper" value="{upper}"/>"#
+                    ),
+                );
+                self.line(depth + 1, "</ownedAttribute>".to_string());
+            }
+        }
+
+        // Indexed, as a property's getter and setter share a name.
+        for (n, method) in cls.methods.iter().filter(|m| !m.is_dunder()).enumerate() {
+            let method_id = escape_xml(&format!("{id}.op.{n}"));
+            self.line(
+                depth + 1,
+                format!(
+                    r#"<ownedOperation xmi:type="uml:Operation" xmi:id="{method_id}" name="{}" visibility="{}" isStatic="{}" isAbstract="{}">"#,
+                    escape_xml(&method.name),
+                    visibility(method.is_public()),
+                    method.is_static(),
+                    method.is_abstract()
+                ),
+            );
+            for arg in method.args.iter() {
+                self.line(
+                    depth + 2,
+                    format!(
+                        r#"<ownedParameter xmi:type="uml:Parameter" xmi:id="{method_id}.param.{}" name="{}" direction="in"{}/>"#,
+                        escape_xml(&arg.name),
+                        escape_xml(&arg.name),
+                        self.typed(arg.dtype.as_deref())
+                    ),
+                );
+            }
+            if let Some(returns) = &method.returns {
+                self.line(
+                    depth + 2,
+                    format!(
+                        r#"<ownedParameter xmi:type="uml:Parameter" xmi:id="{method_id}.return" direction="return"{}/>"#,
+                        self.typed(Some(returns))
+                    ),
+                );
+            }
+            self.line(depth + 1, "</ownedOperation>".to_string());
+        }
+
+        self.line(depth, "</packagedElement>".to_string());
+    }
+
+    /// Writes the association and usage elements, which belong to the model
+    /// rather than to either class.
+    fn relationships(&mut self, depth: usize) {
+        for (n, relation) in self.relations.iter().enumerate() {
+            let from = escape_xml(&self.ids.class(relation.from));
+            let to = escape_xml(&self.ids.target(&relation.to));
+            match (relation.kind, &relation.label) {
+                (RelationKind::Inheritance | RelationKind::Realization, _) => continue,
+                (RelationKind::Dependency, _) => self.line(
+                    depth,
+                    format!(
+                        r#"<packagedElement xmi:type="uml:Usage" xmi:id="R{n}" client="{from}" supplier="{to}"/>"#
+                    ),
+                ),
+                (_, Some(_)) => {
+                    let end = escape_xml(&self.end(n));
+                    self.line(
+                        depth,
+                        format!(
+                            r#"<packagedElement xmi:type="uml:Association" xmi:id="R{n}" memberEnd="{end} R{n}.end">"#
+                        ),
+                    );
+                    self.line(
+                        depth + 1,
+                        format!(
+                            r#"<ownedEnd xmi:type="uml:Property" xmi:id="R{n}.end" type="{from}" association="R{n}"/>"#
+                        ),
+                    );
+                    self.line(depth, "</packagedElement>".to_string());
+                }
+                (_, None) => continue,
+            }
+        }
+    }
+}
+
+/// Renders classes as an XMI 2.5.1 document of a UML 2.5 model, for import
+/// into modelling tools.
+///
+/// Modules become nested packages. Base classes and annotations outside of
+/// the model become stub classes and data types, respectively.
+pub fn classes_to_xmi(classes: &[PyClassInfo]) -> String {
+    let relations = relations(classes);
+    let mut writer = Writer {
+        ids: Ids::new(classes, &relations),
+        classes,
+        relations: &relations,
+        lines: Vec::new(),
+    };
+
+    writer.line(0, r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string());
+    writer.line(
+        0,
+        r#"<xmi:XMI xmi:version="2.5.1" xmlns:xmi="http://www.omg.org/spec/XMI/20131001" xmlns:uml="http://www.omg.org/spec/UML/20161101">"#.to_string(),
+    );
+    writer.line(
+        1,
+        r#"<uml:Model xmi:type="uml:Model" xmi:id="model" name="model">"#.to_string(),
+    );
+
+    let mut root = Package::default();
+    for (i, cls) in classes.iter().enumerate() {
+        let mut package = &mut root;
+        for part in cls.module.split('.').filter(|p| !p.is_empty()) {
+            package = package.children.entry(part).or_default();
+        }
+        package.classes.push(i);
+    }
+    writer.package(2, "", &root);
+    writer.relationships(2);
+
+    let externals = writer.ids.externals.clone();
+    let types = writer.ids.types.clone();
+    for (name, id) in externals {
+        writer.line(
+            2,
+            format!(
+                r#"<packagedElement xmi:type="uml:Class" xmi:id="{}" name="{}"/>"#,
+                escape_xml(&id),
+                escape_xml(&name)
+            ),
+        );
+    }
+    for (name, id) in types {
+        writer.line(
+            2,
+            format!(
+                r#"<packagedElement xmi:type="uml:DataType" xmi:id="{id}" name="{}"/>"#,
+                escape_xml(&name)
+            ),
+        );
+    }
+
+    writer.line(1, "</uml:Model>".to_string());
+    writer.line(0, "</xmi:XMI>".to_string());
+    writer.lines.join(EOL) + EOL
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xmi() {
+        let classes = [
+            PyClassInfo {
+                name: "Customer".to_string(),
+                module: "shop".to_string(),
+                parents: BTreeSet::from(["Base".to_string()]),
+                ..Default::default()
+            },
+            PyClassInfo {
+                name: "Order".to_string(),
+                module: "shop".to_string(),
+                fields: BTreeSet::from([Field {
+                    name: "customer".to_string(),
+                    dtype: Some("Customer".to_string()),
+                    default: None,
+                }]),
+                methods: BTreeSet::from([Method {
+                    name: "total".to_string(),
+                    args: vec![Field {
+                        name: "self".to_string(),
+                        dtype: None,
+                        default: None,
+                    }],
+                    returns: Some("int".to_string()),
+                    decorators: vec![],
+                    docstring: None,
+                }]),
+                ..Default::default()
+            },
+        ];
+
+        #[rustfmt::skip]
+        let expected = [
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<xmi:XMI xmi:version="2.5.1" xmlns:xmi="http://www.omg.org/spec/XMI/20131001" xmlns:uml="http://www.omg.org/spec/UML/20161101">"#,
+            r#"  <uml:Model xmi:type="uml:Model" xmi:id="model" name="model">"#,
+            r#"    <packagedElement xmi:type="uml:Package" xmi:id="P.shop" name="shop">"#,
+            r#"      <packagedElement xmi:type="uml:Class" xmi:id="C.shop.Customer" name="Customer" isAbstract="false">"#,
+            r#"        <generalization xmi:type="uml:Generalization" xmi:id="R0" general="E.Base"/>"#,
+            r#"      </packagedElement>"#,
+            r#"      <packagedElement xmi:type="uml:Class" xmi:id="C.shop.Order" name="Order" isAbstract="false">"#,
+            r#"        <ownedAttribute xmi:type="uml:Property" xmi:id="C.shop.Order.attr.customer" name="customer" visibility="public" isStatic="false" type="C.shop.Customer" aggregation="composite" association="R1">"#,
+            r#"          <lowerValue xmi:type="uml:LiteralInteger" xmi:id="C.shop.Order.attr.customer.lower" value="1"/>"#,
+            r#"          <upperValue xmi:type="uml:LiteralUnlimitedNatural" xmi:id="C.shop.Order.attr.customer.upper" value="1"/>"#,
+            r#"        </ownedAttribute>"#,
+            r#"        <ownedOperation xmi:type="uml:Operation" xmi:id="C.shop.Order.op.0" name="total" visibility="public" isStatic="false" isAbstract="false">"#,
+            r#"          <ownedParameter xmi:type="uml:Parameter" xmi:id="C.shop.Order.op.0.param.self" name="self" direction="in"/>"#,
+            r#"          <ownedParameter xmi:type="uml:Parameter" xmi:id="C.shop.Order.op.0.return" direction="return" type="T1"/>"#,
+            r#"        </ownedOperation>"#,
+            r#"      </packagedElement>"#,
+            r#"    </packagedElement>"#,
+            r#"    <packagedElement xmi:type="uml:Association" xmi:id="R1" memberEnd="C.shop.Order.attr.customer R1.end">"#,
+            r#"      <ownedEnd xmi:type="uml:Property" xmi:id="R1.end" type="C.shop.Order" association="R1"/>"#,
+            r#"    </packagedElement>"#,
+            r#"    <packagedElement xmi:type="uml:Class" xmi:id="E.Base" name="Base"/>"#,
+            r#"    <packagedElement xmi:type="uml:DataType" xmi:id="T1" name="int"/>"#,
+            r#"  </uml:Model>"#,
+            r#"</xmi:XMI>"#,
+            "",
+        ].join(EOL);
+        assert_eq!(classes_to_xmi(&classes), expected);
+    }
+
+    #[test]
+    fn test_xmi_ids() {
+        let field = |name: &str, dtype: Option<&str>| Field {
+            name: name.to_string(),
+            dtype: dtype.map(str::to_string),
+            default: None,
+        };
+        let class = |name: &str, fields| PyClassInfo {
+            name: name.to_string(),
+            fields,
+            ..Default::default()
+        };
+        let classes = [
+            class("A", BTreeSet::new()),
+            class("B", BTreeSet::new()),
+            class(
+                "Holder",
+                BTreeSet::from([
+                    field("item", Some("A | B")),
+                    field("count", None),
+                    field("count", Some("int")),
+                ]),
+            ),
+        ];
+
+        let xmi = classes_to_xmi(&classes);
+        let ids = xmi
+            .split("xmi:id=\"")
+            .skip(1)
+            .filter_map(|s| s.split('"').next())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids.iter().collect::<BTreeSet<_>>().len(),
+            ids.len(),
+            "{ids:?}"
+        );
+        assert_eq!(xmi.matches(r#"name="count""#).count(), 1);
+        assert!(xmi.contains(r#"memberEnd="C.Holder.attr.item R0.end""#));
+        assert!(xmi.contains(r#"memberEnd="C.Holder.attr.item.2 R1.end""#));
+    }
+}